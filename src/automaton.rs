@@ -1,2 +1,13 @@
-mod dfa;
-mod nfa;
+pub mod dfa;
+pub mod nfa;
+
+/// 文字を DOT のラベルとして埋め込めるようにエスケープする
+pub(crate) fn escape_label(c: char) -> String {
+    match c {
+        '"' => "\\\"".to_string(),
+        '\\' => "\\\\".to_string(),
+        // 制御文字は \n などの表記にし、DOT の改行などとして解釈されないよう \ をエスケープする
+        _ if c.is_control() => c.escape_debug().to_string().replace('\\', "\\\\"),
+        _ => c.to_string(),
+    }
+}
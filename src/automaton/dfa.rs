@@ -1 +1,750 @@
+//! NFA から DFA を構築するための型・関数
+//! 部分集合構成法により、NFA の状態の集合を DFA の 1 つの状態に対応させる。
+//! "a|b" の NFA からは、以下の DFA を構築する(0 が開始状態、1 と 2 が受理状態)
+//!
+//! ```text
+//!     a
+//!   0 ──> 1
+//!   0 ──> 2
+//!     b
+//! ```
+//!
+//! 遷移は文字の範囲ごとに持ち、`[a-z]` や `.` のような多くの文字による遷移も 1 文字ずつには展開しない。
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::automaton::{
+    escape_label,
+    nfa::{Anchor, Label, Nfa, NfaState, label_matches},
+};
+
+/// DFA の状態の型
+pub type DfaState = usize;
+
+/// 最小化の際に扱う状態の型。None は遷移先がないこと(死状態)を表す
+type Node = Option<DfaState>;
+
+/// 文字の範囲による遷移の型
+///
+/// (範囲の最初の文字, 範囲の最後の文字, 遷移先) の組で表す
+pub type RangeTransition = (char, char, DfaState);
+
+/// DFA の型
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dfa {
+    /// 入力の先頭から照合を始める場合の開始状態
+    pub start: DfaState,
+    /// 入力の途中から照合を始める場合の開始状態
+    ///
+    /// 入力の先頭を表すアンカー(`^`, `\A`)を通らない点のみが start と異なる
+    pub mid_start: DfaState,
+    /// 受理状態の集合
+    pub accepts: HashSet<DfaState>,
+    /// 入力の末尾に達している場合にのみ受理となる状態の集合(`$`, `\z` を通って受理する状態)
+    pub end_accepts: HashSet<DfaState>,
+    /// 遷移元の状態ごとの、文字の範囲による遷移
+    ///
+    /// 範囲は昇順に並び、互いに重ならない。どの範囲にも含まれない文字による遷移はない
+    pub transitions: HashMap<DfaState, Vec<RangeTransition>>,
+}
+
+impl Dfa {
+    /// 部分集合構成法により、NFA から DFA を構築する
+    pub fn from_nfa(nfa: &Nfa) -> Dfa {
+        let (dfa, _) = Dfa::from_nfa_bounded(nfa, usize::MAX, |_, _| {
+            unreachable!("状態数の上限に達しないため、状態をまとめることはない")
+        });
+        dfa
+    }
+
+    /// 状態数の上限を指定して、NFA から DFA を構築する
+    ///
+    /// 状態数が max_states(1 未満の場合は 1 とする)に達した後に現れた NFA の状態の集合は、
+    /// merge_policy が既存の DFA の状態の集合の中から選んだ状態にまとめる。
+    /// merge_policy は (新しい集合, 既存の状態の集合の一覧) を受け取り、一覧の添字を返す。
+    /// 返す値は一覧の長さ未満でなければならず、そうでない場合はパニックする。
+    /// まとめた状態は両方の集合の和を表すため、構築した DFA は元の式が受理する文字列を必ず受理するが、
+    /// 元の式が受理しない文字列も受理する場合がある(偽陽性はあるが、偽陰性はない)。
+    ///
+    /// 状態をまとめた場合は、戻り値の bool が true となる。
+    pub fn from_nfa_bounded(
+        nfa: &Nfa,
+        max_states: usize,
+        merge_policy: impl Fn(&BTreeSet<NfaState>, &[BTreeSet<NfaState>]) -> DfaState,
+    ) -> (Dfa, bool) {
+        let mut dfa = Dfa {
+            start: 0,
+            mid_start: 0,
+            accepts: HashSet::new(),
+            end_accepts: HashSet::new(),
+            transitions: HashMap::new(),
+        };
+        let mut builder = Builder {
+            ids: HashMap::new(),
+            sets: Vec::new(),
+            queue: VecDeque::new(),
+            max_states: max_states.max(1),
+            merge_policy,
+            merged: false,
+        };
+
+        let start: BTreeSet<NfaState> = BTreeSet::from([nfa.start]);
+        let start_states: BTreeSet<NfaState> = closure(nfa, &start, &[Anchor::Start]);
+        // 空の入力では入力の先頭と末尾が一致するため、"$^" のように両方のアンカーを交互に通れる。
+        // 末尾のアンカーのみを通る場合と受理するかが変わるときは、同じ集合の状態とは別の状態にする
+        let accepts_at = |states: &BTreeSet<NfaState>, anchors: &[Anchor]| -> bool {
+            closure(nfa, states, anchors)
+                .iter()
+                .any(|s| nfa.accepts.contains(s))
+        };
+        dfa.start = if accepts_at(&start_states, &[Anchor::Start, Anchor::End])
+            == accepts_at(&start_states, &[Anchor::End])
+        {
+            builder.id(start_states)
+        } else {
+            builder.distinct_id(start_states)
+        };
+        dfa.mid_start = builder.id(closure(nfa, &start, &[]));
+
+        while let Some(from) = builder.queue.pop_front() {
+            let states: BTreeSet<NfaState> = builder.sets[from].clone();
+            // 入力の先頭から始める場合の開始状態で入力の末尾に達するのは、空の入力の場合のみ
+            let end_anchors: &[Anchor] = if from == dfa.start {
+                &[Anchor::Start, Anchor::End]
+            } else {
+                &[Anchor::End]
+            };
+            if states.iter().any(|s| nfa.accepts.contains(s)) {
+                dfa.accepts.insert(from);
+            } else if accepts_at(&states, end_anchors) {
+                dfa.end_accepts.insert(from);
+            }
+
+            // 文字を消費する遷移のラベルの範囲の境界で文字全体を区間に分けると、
+            // 同じ区間の文字はどれも同じ遷移をたどるため、区間の最初の文字で遷移先を求める
+            let edges: Vec<(&Label, NfaState)> = states
+                .iter()
+                .flat_map(|&s| nfa.transitions_from(s))
+                .filter(|(label, _)| !matches!(label, Label::Epsilon | Label::Anchor(_)))
+                .map(|(label, to)| (label, *to))
+                .collect();
+            let mut bounds: BTreeSet<u32> = BTreeSet::new();
+            for (label, _) in &edges {
+                match label {
+                    Label::Char(c) => bounds.extend([*c as u32, *c as u32 + 1]),
+                    Label::Class(ranges) | Label::AnyExcept(ranges) => {
+                        bounds.extend(ranges.iter().flat_map(|&(s, e)| [s as u32, e as u32 + 1]));
+                    }
+                    Label::Epsilon | Label::Anchor(_) => {}
+                }
+            }
+
+            let mut intervals: Vec<(char, char, Option<DfaState>)> = Vec::new();
+            for (start, end) in split_ranges(&bounds) {
+                let next: BTreeSet<NfaState> = edges
+                    .iter()
+                    .filter(|(label, _)| label_matches(label, start))
+                    .map(|&(_, to)| to)
+                    .collect();
+                let to: Option<DfaState> =
+                    (!next.is_empty()).then(|| builder.id(closure(nfa, &next, &[])));
+                intervals.push((start, end, to));
+            }
+            dfa.transitions.remove(&from);
+            let transitions: Vec<RangeTransition> = merge_ranges(intervals);
+            if !transitions.is_empty() {
+                dfa.transitions.insert(from, transitions);
+            }
+        }
+
+        (dfa, builder.merged)
+    }
+
+    /// Hopcroft のアルゴリズムにより、同じ言語を受理する状態数が最小の DFA を返す
+    ///
+    /// 遷移がないことを 1 つの状態(死状態)とみなし、受理状態・入力の末尾でのみ受理となる状態・
+    /// それ以外の状態の分割から始めて、遷移先が異なる状態を含むブロックを分割していく。
+    /// 開始状態から到達できない状態は取り除き、番号は開始状態から順に振り直す。
+    pub fn minimize(self) -> Dfa {
+        // すべての状態の遷移の範囲の境界で文字全体を分けた範囲を、1 つの記号として扱う
+        let mut bounds: BTreeSet<u32> = BTreeSet::new();
+        let mut nodes: BTreeSet<Node> = BTreeSet::from([None]);
+        nodes.extend([Some(self.start), Some(self.mid_start)]);
+        for (&from, transitions) in &self.transitions {
+            nodes.insert(Some(from));
+            for &(start, end, to) in transitions {
+                bounds.extend([start as u32, end as u32 + 1]);
+                nodes.insert(Some(to));
+            }
+        }
+        let symbols: Vec<(char, char)> = split_ranges(&bounds);
+        nodes.extend(
+            self.accepts
+                .iter()
+                .chain(&self.end_accepts)
+                .copied()
+                .map(Some),
+        );
+
+        let step = |node: Node, (c, _): (char, char)| -> Node { self.next_state(node?, c) };
+        let kind = |node: Node| -> (bool, bool) {
+            node.map_or((false, false), |state| {
+                (
+                    self.accepts.contains(&state),
+                    self.end_accepts.contains(&state),
+                )
+            })
+        };
+
+        // 状態に 0 から順に添字を割り当て、記号ごとに遷移の逆引きを作る
+        let nodes: Vec<Node> = nodes.into_iter().collect();
+        let index: HashMap<Node, usize> = nodes.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let mut inverse: Vec<Vec<Vec<usize>>> = vec![vec![Vec::new(); nodes.len()]; symbols.len()];
+        for (i, &node) in nodes.iter().enumerate() {
+            for (a, &symbol) in symbols.iter().enumerate() {
+                inverse[a][index[&step(node, symbol)]].push(i);
+            }
+        }
+
+        // 分割は、状態ごとのブロックの番号・ブロックごとの状態の一覧・一覧の中での状態の位置で表す
+        let mut initial: BTreeMap<(bool, bool), Vec<usize>> = BTreeMap::new();
+        for (i, &node) in nodes.iter().enumerate() {
+            initial.entry(kind(node)).or_default().push(i);
+        }
+        let mut members: Vec<Vec<usize>> = initial.into_values().collect();
+        let mut block_of: Vec<usize> = vec![0; nodes.len()];
+        let mut position: Vec<usize> = vec![0; nodes.len()];
+        for (block, list) in members.iter().enumerate() {
+            for (pos, &i) in list.iter().enumerate() {
+                block_of[i] = block;
+                position[i] = pos;
+            }
+        }
+        let mut work: Vec<usize> = (0..members.len()).collect();
+
+        let mut is_source: Vec<bool> = vec![false; nodes.len()];
+        let mut sources_of: HashMap<usize, Vec<usize>> = HashMap::new();
+        while let Some(splitter) = work.pop() {
+            let targets: Vec<usize> = members[splitter].clone();
+            for inverse in &inverse {
+                // 遷移先が splitter に含まれる状態を、ブロックごとに集める
+                for &to in &targets {
+                    for &from in &inverse[to] {
+                        is_source[from] = true;
+                        sources_of.entry(block_of[from]).or_default().push(from);
+                    }
+                }
+                for (block, sources) in sources_of.drain() {
+                    let rest: usize = members[block].len() - sources.len();
+                    if rest > 0 {
+                        // 小さい方を新しいブロックに移すため、移す状態の数は sources の数以下となる
+                        let moved: Vec<usize> = if sources.len() <= rest {
+                            sources.clone()
+                        } else {
+                            members[block]
+                                .iter()
+                                .copied()
+                                .filter(|&i| !is_source[i])
+                                .collect()
+                        };
+                        let new_block: usize = members.len();
+                        members.push(Vec::new());
+                        for i in moved {
+                            let list: &mut Vec<usize> = &mut members[block];
+                            list.swap_remove(position[i]);
+                            if let Some(&swapped) = list.get(position[i]) {
+                                position[swapped] = position[i];
+                            }
+                            block_of[i] = new_block;
+                            position[i] = members[new_block].len();
+                            members[new_block].push(i);
+                        }
+                        // 元のブロックが作業リストにある場合は両方が、ない場合は小さい方が調べられる
+                        work.push(new_block);
+                    }
+                    for i in sources {
+                        is_source[i] = false;
+                    }
+                }
+            }
+        }
+        let dead: usize = block_of[index[&None]];
+
+        // 開始状態から順に、ブロックに新しい番号を割り当てる
+        let mut dfa = Dfa {
+            start: 0,
+            mid_start: 0,
+            accepts: HashSet::new(),
+            end_accepts: HashSet::new(),
+            transitions: HashMap::new(),
+        };
+        let mut ids: HashMap<usize, DfaState> = HashMap::new();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        let mut id = |block: usize, queue: &mut VecDeque<usize>| -> DfaState {
+            let next_id: DfaState = ids.len();
+            *ids.entry(block).or_insert_with(|| {
+                queue.push_back(block);
+                next_id
+            })
+        };
+        dfa.start = id(block_of[index[&Some(self.start)]], &mut queue);
+        dfa.mid_start = id(block_of[index[&Some(self.mid_start)]], &mut queue);
+
+        let mut from: DfaState = 0;
+        while let Some(block) = queue.pop_front() {
+            // 同じブロックの状態は等価なため、任意の 1 つの遷移を調べればよい
+            let representative: Node = nodes[members[block][0]];
+            let (accept, end_accept) = kind(representative);
+            if accept {
+                dfa.accepts.insert(from);
+            }
+            if end_accept {
+                dfa.end_accepts.insert(from);
+            }
+
+            let intervals: Vec<(char, char, Option<DfaState>)> = symbols
+                .iter()
+                .map(|&(start, end)| {
+                    let to: usize = block_of[index[&step(representative, (start, end))]];
+                    (start, end, (to != dead).then(|| id(to, &mut queue)))
+                })
+                .collect();
+            let transitions: Vec<RangeTransition> = merge_ranges(intervals);
+            if !transitions.is_empty() {
+                dfa.transitions.insert(from, transitions);
+            }
+            from += 1;
+        }
+
+        dfa
+    }
+
+    /// Graphviz の DOT 形式の文字列を返す
+    ///
+    /// 受理状態(入力の末尾でのみ受理となる状態を含む)は二重丸で描き、開始状態には矢印を付ける。
+    /// 入力の途中から始める場合の開始状態が異なる場合は、その状態に点線の矢印を付ける。
+    /// 文字の範囲による遷移には、`a` または `a-z` のように範囲をラベルとして付ける。
+    pub fn to_dot(&self) -> String {
+        let mut states: BTreeSet<DfaState> = BTreeSet::from([self.start, self.mid_start]);
+        for (&from, transitions) in &self.transitions {
+            states.insert(from);
+            states.extend(transitions.iter().map(|&(_, _, to)| to));
+        }
+        states.extend(self.accepts.iter().chain(&self.end_accepts));
+
+        let mut dot = String::from("digraph dfa {\n    rankdir=LR;\n");
+        dot.push_str("    start [shape=point];\n");
+        dot.push_str(&format!("    start -> {};\n", self.start));
+        if self.mid_start != self.start {
+            dot.push_str("    mid_start [shape=point];\n");
+            dot.push_str(&format!(
+                "    mid_start -> {} [style=dashed];\n",
+                self.mid_start
+            ));
+        }
+        for state in states {
+            let shape: &str = if self.is_accept(state, true) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            dot.push_str(&format!("    {state} [shape={shape}];\n"));
+        }
+
+        let transitions: BTreeMap<&DfaState, &Vec<RangeTransition>> =
+            self.transitions.iter().collect();
+        for (from, transitions) in transitions {
+            for &(start, end, to) in transitions {
+                let label: String = if start == end {
+                    escape_label(start)
+                } else {
+                    format!("{}-{}", escape_label(start), escape_label(end))
+                };
+                dot.push_str(&format!("    {from} -> {to} [label=\"{label}\"];\n"));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 入力全体を読み進め、受理状態で終わるかを返す
+    ///
+    /// 遷移がない文字を読んだ時点で false を返す
+    pub fn accepts(&self, input: &str) -> bool {
+        let mut state: DfaState = self.start;
+        for c in input.chars() {
+            match self.next_state(state, c) {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+        self.is_accept(state, true)
+    }
+
+    /// state が受理状態かを返す
+    ///
+    /// at_end が true の場合は入力の末尾に達しているものとし、end_accepts に含まれる状態も受理とする
+    pub fn is_accept(&self, state: DfaState, at_end: bool) -> bool {
+        self.accepts.contains(&state) || (at_end && self.end_accepts.contains(&state))
+    }
+
+    /// state から文字 c で遷移した先の状態を返す。遷移がない場合は None を返す
+    pub fn next_state(&self, state: DfaState, c: char) -> Option<DfaState> {
+        let transitions: &[RangeTransition] = self.transitions.get(&state)?;
+        // 範囲は昇順に並ぶため、c 以上で終わる最初の範囲を二分探索で求める
+        let i: usize = transitions.partition_point(|&(_, end, _)| end < c);
+        transitions
+            .get(i)
+            .filter(|&&(start, _, _)| start <= c)
+            .map(|&(_, _, to)| to)
+    }
+}
+
+/// DFA の状態に番号を割り当て、遷移を調べていない状態を保持する型
+struct Builder<F> {
+    ids: HashMap<BTreeSet<NfaState>, DfaState>,
+    sets: Vec<BTreeSet<NfaState>>, // 各 DFA の状態が表す NFA の状態の集合
+    queue: VecDeque<DfaState>,
+    max_states: usize,
+    merge_policy: F,
+    merged: bool,
+}
+
+impl<F: Fn(&BTreeSet<NfaState>, &[BTreeSet<NfaState>]) -> DfaState> Builder<F> {
+    /// NFA の状態の集合に対応する DFA の状態を返す
+    ///
+    /// 初めて現れた集合の場合は新しい番号を割り当て、遷移を調べるためにキューへ追加する。
+    /// 状態数が上限に達している場合は既存の状態にまとめ、その状態の遷移を調べ直す。
+    fn id(&mut self, states: BTreeSet<NfaState>) -> DfaState {
+        if let Some(&id) = self.ids.get(&states) {
+            return id;
+        }
+        if self.sets.len() < self.max_states {
+            let id: DfaState = self.sets.len();
+            self.ids.insert(states.clone(), id);
+            self.sets.push(states);
+            self.queue.push_back(id);
+            return id;
+        }
+
+        let id: DfaState = (self.merge_policy)(&states, &self.sets);
+        assert!(
+            id < self.sets.len(),
+            "merge_policy は状態の数({})未満の番号を返す必要があるが、{id} を返した",
+            self.sets.len()
+        );
+        self.merged = true;
+        let union: BTreeSet<NfaState> = self.sets[id].union(&states).copied().collect();
+        self.ids.insert(states, id);
+        if union != self.sets[id] {
+            self.ids.insert(union.clone(), id);
+            self.sets[id] = union;
+            self.queue.push_back(id);
+        }
+        id
+    }
+
+    /// 同じ集合の状態があっても、ids に登録せずに新しい番号を割り当てる
+    ///
+    /// 以降に同じ集合が現れた場合は、この状態とは別の状態になる
+    fn distinct_id(&mut self, states: BTreeSet<NfaState>) -> DfaState {
+        let id: DfaState = self.sets.len();
+        self.sets.push(states);
+        self.queue.push_back(id);
+        id
+    }
+}
+
+/// states から ε 遷移と anchors に含まれるアンカーによる遷移で到達できる状態の集合を、
+/// DFA の状態のキーとして扱えるよう BTreeSet で返す(`Nfa::closure_with_anchors` を参照)
+fn closure(nfa: &Nfa, states: &BTreeSet<NfaState>, anchors: &[Anchor]) -> BTreeSet<NfaState> {
+    let states: Vec<NfaState> = states.iter().copied().collect();
+    nfa.closure_with_anchors(&states, anchors)
+        .into_iter()
+        .collect()
+}
+
+/// 文字全体を、bounds に含まれる符号位置の直前で区切った範囲に分けて昇順に返す
+///
+/// サロゲートの符号位置は文字ではないため、範囲には含めない
+fn split_ranges(bounds: &BTreeSet<u32>) -> Vec<(char, char)> {
+    let mut points: Vec<u32> = vec![0];
+    points.extend(bounds.iter().filter(|&&b| 0 < b && b <= char::MAX as u32));
+    points.push(char::MAX as u32 + 1);
+
+    let mut ranges: Vec<(char, char)> = Vec::new();
+    for pair in points.windows(2) {
+        let start: u32 = if (0xD800..0xE000).contains(&pair[0]) {
+            0xE000
+        } else {
+            pair[0]
+        };
+        let end: u32 = if (0xD800..0xE000).contains(&(pair[1] - 1)) {
+            0xD7FF
+        } else {
+            pair[1] - 1
+        };
+        if let (Some(start), Some(end)) = (char::from_u32(start), char::from_u32(end))
+            && start <= end
+        {
+            ranges.push((start, end));
+        }
+    }
+    ranges
+}
+
+/// 文字全体を昇順に隙間なく覆う範囲の一覧から、隣り合う範囲のうち遷移先が同じものをまとめ、
+/// 遷移先がない範囲を取り除く
+fn merge_ranges(intervals: Vec<(char, char, Option<DfaState>)>) -> Vec<RangeTransition> {
+    let mut merged: Vec<(char, char, Option<DfaState>)> = Vec::new();
+    for (start, end, to) in intervals {
+        match merged.last_mut() {
+            Some(last) if last.2 == to => last.1 = end,
+            _ => merged.push((start, end, to)),
+        }
+    }
+    merged
+        .into_iter()
+        .filter_map(|(start, end, to)| Some((start, end, to?)))
+        .collect()
+}
+
+// ----- テストコード・試し -----
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::{
+        automaton::{
+            dfa::{Dfa, DfaState, RangeTransition},
+            nfa::{Nfa, NfaState},
+        },
+        parser::parse,
+    };
+
+    /// 式をパースして DFA を構築する
+    fn build(pattern: &str) -> Dfa {
+        let mut state: NfaState = 0;
+        let nfa: Nfa = Nfa::from_ast(&parse(pattern).unwrap(), &mut state);
+        Dfa::from_nfa(&nfa)
+    }
+
+    #[test]
+    fn test_from_nfa() {
+        // ----- "a|b" が入力されたケース -----
+        let dfa: Dfa = build("a|b");
+        let expect: HashMap<DfaState, Vec<RangeTransition>> =
+            HashMap::from([(0, vec![('a', 'a', 1), ('b', 'b', 2)])]);
+        assert_eq!(dfa.transitions, expect);
+        assert_eq!(dfa.start, 0);
+        assert_eq!(dfa.accepts, HashSet::from([1, 2]));
+    }
+
+    #[test]
+    fn test_from_nfa_class() {
+        // ----- "[a-cx]|[b-d]" は範囲の境界で遷移を分ける -----
+        let dfa: Dfa = build("[a-cx]|[b-d]");
+        let expect: HashMap<DfaState, Vec<RangeTransition>> = HashMap::from([(
+            0,
+            vec![('a', 'a', 1), ('b', 'c', 2), ('d', 'd', 3), ('x', 'x', 1)],
+        )]);
+        assert_eq!(dfa.transitions, expect);
+        assert_eq!(dfa.accepts, HashSet::from([1, 2, 3]));
+
+        // ----- 範囲が広い場合も、範囲のまま遷移を持つ -----
+        let dfa: Dfa = build("[\u{1}-\u{10FFFF}]");
+        let expect: HashMap<DfaState, Vec<RangeTransition>> =
+            HashMap::from([(0, vec![('\u{1}', '\u{10FFFF}', 1)])]);
+        assert_eq!(dfa.transitions, expect);
+        assert_eq!(dfa.next_state(0, '\u{0}'), None);
+        assert_eq!(dfa.next_state(0, 'あ'), Some(1));
+    }
+
+    #[test]
+    fn test_from_nfa_any_char() {
+        // ----- "a\Db" が入力されたケース -----
+        let dfa: Dfa = build("a\\Db");
+        let after_a: DfaState = dfa.next_state(dfa.start, 'a').unwrap();
+        let after_x: DfaState = dfa.next_state(after_a, 'x').unwrap();
+        assert_eq!(dfa.next_state(after_a, 'b'), Some(after_x));
+        assert!(dfa.accepts.contains(&dfa.next_state(after_x, 'b').unwrap()));
+
+        // 除外した文字による遷移はない
+        assert_eq!(dfa.next_state(after_a, '1'), None);
+    }
+
+    #[test]
+    fn test_accepts() {
+        // ----- "a*b" が入力されたケース -----
+        let dfa: Dfa = build("a*b");
+        assert!(dfa.accepts("b"));
+        assert!(dfa.accepts("ab"));
+        assert!(dfa.accepts("aaab"));
+        assert!(!dfa.accepts(""));
+        assert!(!dfa.accepts("aa"));
+        assert!(!dfa.accepts("abb"));
+        assert!(!dfa.accepts("ba"));
+
+        // ----- 手で構築した DFA のケース -----
+        // 0 -x-> 1, 1 -(それ以外の文字)-> 1。1 が受理状態
+        let dfa = Dfa {
+            start: 0,
+            mid_start: 0,
+            accepts: HashSet::from([1]),
+            end_accepts: HashSet::new(),
+            transitions: HashMap::from([(0, vec![('x', 'x', 1)]), (1, vec![('\0', char::MAX, 1)])]),
+        };
+        assert!(dfa.accepts("x"));
+        assert!(dfa.accepts("xyz"));
+        assert!(!dfa.accepts("yx"));
+    }
+
+    /// DFA に現れる状態の数を返す
+    fn state_count(dfa: &Dfa) -> usize {
+        let mut states: HashSet<DfaState> = HashSet::from([dfa.start, dfa.mid_start]);
+        for (&from, transitions) in &dfa.transitions {
+            states.insert(from);
+            states.extend(transitions.iter().map(|&(_, _, to)| to));
+        }
+        states.extend(&dfa.accepts);
+        states.extend(&dfa.end_accepts);
+        states.len()
+    }
+
+    #[test]
+    fn test_from_nfa_bounded() {
+        // ----- "(a|b)*abb" が入力されたケース -----
+        let mut state: NfaState = 0;
+        let nfa: Nfa = Nfa::from_ast(&parse("(a|b)*abb").unwrap(), &mut state);
+        let exact: Dfa = Dfa::from_nfa(&nfa);
+
+        // 共通する NFA の状態が最も多い状態にまとめる
+        let (bounded, merged) = Dfa::from_nfa_bounded(&nfa, 3, |states, candidates| {
+            (0..candidates.len())
+                .max_by_key(|&id| candidates[id].intersection(states).count())
+                .unwrap()
+        });
+        assert!(merged);
+        assert!(state_count(&bounded) <= 3);
+        assert!(state_count(&bounded) < state_count(&exact));
+
+        // 元の DFA が受理する文字列は、すべて受理する
+        let inputs = ["abb", "aabb", "babb", "ababb", "bbabb", "ab", "", "abba"];
+        for input in inputs {
+            if exact.accepts(input) {
+                assert!(bounded.accepts(input), "{input}");
+            }
+        }
+
+        // 上限に達しない場合は、状態をまとめない
+        let (unbounded, merged) = Dfa::from_nfa_bounded(&nfa, 100, |_, _| 0);
+        assert!(!merged);
+        assert_eq!(unbounded, exact);
+    }
+
+    #[test]
+    #[should_panic(expected = "merge_policy は状態の数(3)未満の番号を返す必要があるが、5 を返した")]
+    fn test_from_nfa_bounded_invalid_policy() {
+        // ----- 存在しない状態の番号を返すケース -----
+        let mut state: NfaState = 0;
+        let nfa: Nfa = Nfa::from_ast(&parse("(a|b)*abb").unwrap(), &mut state);
+        Dfa::from_nfa_bounded(&nfa, 3, |_, _| 5);
+    }
+
+    #[test]
+    fn test_minimize() {
+        let inputs = [
+            "", "a", "b", "c", "ab", "ac", "abb", "aabb", "babb", "abab", "ababb", "bbabb", "abba",
+        ];
+        // (式, 最小化した DFA の状態数)
+        let cases = [
+            ("a|b", 2),
+            ("(a|a)b", 3),
+            ("ab|ac", 3),
+            ("(a|b)*abb", 4),
+            ("(a|b)*a(a|b){4}|(b|a)*a(b|a){4}", 32),
+        ];
+        for (pattern, expect) in cases {
+            let dfa: Dfa = build(pattern);
+            let minimized: Dfa = dfa.clone().minimize();
+            assert_eq!(state_count(&minimized), expect, "{pattern}");
+            assert!(state_count(&minimized) <= state_count(&dfa), "{pattern}");
+            for input in inputs {
+                assert_eq!(
+                    minimized.accepts(input),
+                    dfa.accepts(input),
+                    "{pattern}: {input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize_any_char_and_anchor() {
+        let inputs = ["", "a", "ab", "a1", "ax", "xa", "a1b", "axb", "abb"];
+        for pattern in ["a\\D", "a.b|a1b", "^a$|ab$", "a\\d*", "$^|a"] {
+            let dfa: Dfa = build(pattern);
+            let minimized: Dfa = dfa.clone().minimize();
+            assert!(state_count(&minimized) <= state_count(&dfa), "{pattern}");
+            for input in inputs {
+                assert_eq!(
+                    minimized.accepts(input),
+                    dfa.accepts(input),
+                    "{pattern}: {input}"
+                );
+            }
+            // 入力の途中から始める場合も同じ結果になる
+            let mid = |d: &Dfa, input: &str| {
+                let mut state: Option<DfaState> = Some(d.mid_start);
+                for c in input.chars() {
+                    state = state.and_then(|s| d.next_state(s, c));
+                }
+                state.is_some_and(|s| d.is_accept(s, true))
+            };
+            for input in inputs {
+                assert_eq!(
+                    mid(&minimized, input),
+                    mid(&dfa, input),
+                    "{pattern}: {input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        // ----- "a|b" が入力されたケース -----
+        let dot: String = build("a|b").to_dot();
+        assert!(dot.starts_with("digraph dfa {"));
+        assert!(dot.contains("start -> 0;"));
+        assert!(dot.contains("0 [shape=circle];"));
+        assert!(dot.contains("1 [shape=doublecircle];"));
+        assert!(dot.contains("0 -> 1 [label=\"a\"];"));
+        assert!(dot.contains("0 -> 2 [label=\"b\"];"));
+        assert_eq!(dot.matches("[label=").count(), 2);
+
+        // ----- 特殊文字をエスケープするケース -----
+        let dot: String = build("\"|\\\\|\\n").to_dot();
+        assert!(dot.contains("[label=\"\\\"\"]"));
+        assert!(dot.contains("[label=\"\\\\\"]"));
+        assert!(dot.contains("[label=\"\\\\n\"]"));
+
+        // ----- "[a-c]." は範囲をラベルとして描く -----
+        let dot: String = build("[a-c].").to_dot();
+        assert!(dot.contains("0 -> 1 [label=\"a-c\"];"));
+        assert!(dot.contains("1 -> 2 [label=\"\\\\0-\u{10FFFF}\"];"));
+    }
+
+    #[test]
+    fn test_from_nfa_anchor() {
+        // ----- "^a$" が入力されたケース -----
+        let dfa: Dfa = build("^a$");
+        let after_a: DfaState = dfa.next_state(dfa.start, 'a').unwrap();
+        assert!(!dfa.accepts.contains(&after_a));
+        assert!(dfa.end_accepts.contains(&after_a));
+
+        // 入力の途中からは ^ を通れない
+        assert_eq!(dfa.next_state(dfa.mid_start, 'a'), None);
+    }
+}
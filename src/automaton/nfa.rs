@@ -1 +1,740 @@
+//! 抽象構文木(Ast)から NFA を構築するための型・関数
+//! Thompson の構成法により、Ast の各ノードを開始状態と受理状態を 1 つずつ持つ NFA の断片に変換し、
+//! 断片同士を ε 遷移でつなぐ。
+//! "a|b" が入力された場合、以下の NFA に変換する(4 が開始状態、5 が受理状態)
+//!
+//! ```text
+//!     ε     a     ε
+//!   4 ──> 0 ──> 1 ──> 5
+//!   4 ──> 2 ──> 3 ──> 5
+//!     ε     b     ε
+//! ```
 
+use std::collections::{BTreeSet, HashSet};
+
+use crate::{automaton::escape_label, parser::Ast};
+
+/// NFA の状態の型
+pub type NfaState = usize;
+
+/// 入力上の位置に関する条件の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    Start, // 入力の先頭(^, \A)
+    End,   // 入力の末尾($, \z)
+}
+
+/// 遷移のラベルの型
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Label {
+    Epsilon,                      // 文字を消費しない遷移(ε 遷移)
+    Char(char),                   // 1 文字による遷移
+    Class(Vec<(char, char)>),     // 範囲のいずれかに含まれる 1 文字による遷移(`[a-z]` など)
+    AnyExcept(Vec<(char, char)>), // 除外する範囲に含まれない任意の 1 文字による遷移(`.`, `\D` など)
+    Anchor(Anchor),               // 入力の先頭・末尾でのみ通ることができる、文字を消費しない遷移
+}
+
+/// NFA の型
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nfa {
+    /// 開始状態
+    pub start: NfaState,
+    /// 受理状態の集合
+    pub accepts: HashSet<NfaState>,
+    /// 遷移元の状態ごとの遷移。transitions[from] は from から出る (ラベル, 遷移先) の一覧
+    pub transitions: Vec<Vec<(Label, NfaState)>>,
+}
+
+impl Nfa {
+    /// Ast から NFA を構築する
+    ///
+    /// state は次に割り当てる状態の番号で、状態を 1 つ生成するごとに加算される。
+    /// NFA は入力を受理するかどうかのみを扱うため、量指定子の貪欲・非貪欲の区別は無視する。
+    pub fn from_ast(ast: &Ast, state: &mut NfaState) -> Nfa {
+        let mut nfa = Nfa {
+            start: 0,
+            accepts: HashSet::new(),
+            transitions: Vec::new(),
+        };
+        let (start, accept) = nfa.add_fragment(ast, state);
+        nfa.start = start;
+        nfa.accepts.insert(accept);
+        nfa
+    }
+
+    /// from から to への遷移を追加する
+    pub fn add_transition(&mut self, from: NfaState, label: Label, to: NfaState) {
+        let len: usize = from.max(to) + 1;
+        if self.transitions.len() < len {
+            self.transitions.resize_with(len, Vec::new);
+        }
+        self.transitions[from].push((label, to));
+    }
+
+    /// state から出る遷移の (ラベル, 遷移先) の一覧を返す
+    pub fn transitions_from(&self, state: NfaState) -> &[(Label, NfaState)] {
+        self.transitions.get(state).map_or(&[], Vec::as_slice)
+    }
+
+    /// state から label による 1 回の遷移で到達できる状態を返す
+    ///
+    /// label が Some(c) の場合は、範囲・任意の 1 文字による遷移を含め、c で通れる遷移の遷移先を返す。
+    /// label が None の場合は ε 遷移の遷移先を返す
+    pub fn next_states(&self, state: NfaState, label: Option<char>) -> Vec<NfaState> {
+        self.transitions_from(state)
+            .iter()
+            .filter(|(l, _)| match label {
+                Some(c) => label_matches(l, c),
+                None => *l == Label::Epsilon,
+            })
+            .map(|&(_, to)| to)
+            .collect()
+    }
+
+    /// state から出る遷移のラベルを、重複を除いて現れた順に返す
+    ///
+    /// 範囲・任意の 1 文字・アンカーによる遷移のラベルも含む。遷移がない場合のみ空になる
+    pub fn next_chars(&self, state: NfaState) -> Vec<Label> {
+        let mut seen: HashSet<&Label> = HashSet::new();
+        self.transitions_from(state)
+            .iter()
+            .map(|(label, _)| label)
+            .filter(|&label| seen.insert(label))
+            .cloned()
+            .collect()
+    }
+
+    /// states から ε 遷移のみで到達できる状態の集合(states 自身を含む)を返す
+    pub fn epsilon_closure(&self, states: &[NfaState]) -> HashSet<NfaState> {
+        self.closure_with_anchors(states, &[])
+    }
+
+    /// states から ε 遷移と anchors に含まれるアンカーによる遷移で到達できる状態の集合
+    /// (states 自身を含む)を返す
+    ///
+    /// 複数のアンカーを指定した場合は、交互に何度でも通る
+    pub fn closure_with_anchors(
+        &self,
+        states: &[NfaState],
+        anchors: &[Anchor],
+    ) -> HashSet<NfaState> {
+        let mut closure: HashSet<NfaState> = states.iter().copied().collect();
+        let mut stack: Vec<NfaState> = states.to_vec();
+        while let Some(state) = stack.pop() {
+            for (label, to) in self.transitions_from(state) {
+                let passable: bool = match label {
+                    Label::Epsilon => true,
+                    Label::Anchor(anchor) => anchors.contains(anchor),
+                    Label::Char(_) | Label::Class(_) | Label::AnyExcept(_) => false,
+                };
+                if passable && closure.insert(*to) {
+                    stack.push(*to);
+                }
+            }
+        }
+        closure
+    }
+
+    /// ε 遷移を取り除いた、同じ言語を受理する NFA を返す
+    ///
+    /// 各状態から ε 遷移で到達できる状態の遷移を、その状態自身の遷移として持たせ、
+    /// ε 遷移で受理状態に到達できる状態を受理状態とする。
+    /// 状態の番号は変えず、開始状態から到達できない状態は取り除く。
+    ///
+    /// アンカーによる遷移は入力上の位置に依存するため取り除かず、文字を消費しない遷移として残す。
+    /// そのため、アンカーを含む NFA では、結果のすべての遷移が文字を消費するとは限らない。
+    pub fn remove_epsilon(&self) -> Nfa {
+        let mut nfa = Nfa {
+            start: self.start,
+            accepts: HashSet::new(),
+            transitions: Vec::new(),
+        };
+        let mut visited: HashSet<NfaState> = HashSet::from([self.start]);
+        let mut stack: Vec<NfaState> = vec![self.start];
+        while let Some(state) = stack.pop() {
+            let mut closure: Vec<NfaState> = self.epsilon_closure(&[state]).into_iter().collect();
+            closure.sort_unstable();
+            if closure.iter().any(|s| self.accepts.contains(s)) {
+                nfa.accepts.insert(state);
+            }
+
+            for &from in &closure {
+                for (label, to) in self.transitions_from(from) {
+                    if *label == Label::Epsilon {
+                        continue;
+                    }
+                    nfa.add_transition(state, label.clone(), *to);
+                    if visited.insert(*to) {
+                        stack.push(*to);
+                    }
+                }
+            }
+        }
+        nfa
+    }
+
+    /// Graphviz の DOT 形式の文字列を返す
+    ///
+    /// 受理状態は二重丸で描き、開始状態には矢印を付ける。
+    /// ε 遷移には ε を、範囲による遷移には `[...]` を、任意の 1 文字による遷移には `.` または `[^...]` を、
+    /// アンカーによる遷移には `^` または `$` をラベルとして付ける。
+    pub fn to_dot(&self) -> String {
+        let mut states: BTreeSet<NfaState> = BTreeSet::from([self.start]);
+        states.extend(&self.accepts);
+        for (from, transitions) in self.transitions.iter().enumerate() {
+            if !transitions.is_empty() {
+                states.insert(from);
+            }
+            states.extend(transitions.iter().map(|&(_, to)| to));
+        }
+
+        let mut dot = String::from("digraph nfa {\n    rankdir=LR;\n");
+        dot.push_str("    start [shape=point];\n");
+        dot.push_str(&format!("    start -> {};\n", self.start));
+        for state in states {
+            let shape: &str = if self.accepts.contains(&state) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            dot.push_str(&format!("    {state} [shape={shape}];\n"));
+        }
+
+        for (from, transitions) in self.transitions.iter().enumerate() {
+            for (label, to) in transitions {
+                let label: String = match label {
+                    Label::Epsilon => "ε".to_string(),
+                    Label::Char(c) => escape_label(*c),
+                    Label::Class(ranges) => format!("[{}]", ranges_label(ranges)),
+                    Label::AnyExcept(excluded) if excluded.is_empty() => ".".to_string(),
+                    Label::AnyExcept(excluded) => format!("[^{}]", ranges_label(excluded)),
+                    Label::Anchor(Anchor::Start) => "^".to_string(),
+                    Label::Anchor(Anchor::End) => "$".to_string(),
+                };
+                dot.push_str(&format!("    {from} -> {to} [label=\"{label}\"];\n"));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Ast に対応する断片の遷移を追加し、断片の (開始状態, 受理状態) を返す
+    fn add_fragment(&mut self, ast: &Ast, state: &mut NfaState) -> (NfaState, NfaState) {
+        match ast {
+            Ast::Char(c) => {
+                let (start, accept) = (new_state(state), new_state(state));
+                self.add_transition(start, Label::Char(*c), accept);
+                (start, accept)
+            }
+            Ast::AnyChar => {
+                let (start, accept) = (new_state(state), new_state(state));
+                self.add_transition(start, Label::AnyExcept(Vec::new()), accept);
+                (start, accept)
+            }
+            Ast::Class(ranges) => {
+                // 範囲を 1 文字ずつに展開せず、範囲のまま 1 つの遷移とする
+                let (start, accept) = (new_state(state), new_state(state));
+                self.add_transition(start, Label::Class(ranges.clone()), accept);
+                (start, accept)
+            }
+            Ast::NegClass(ranges) => {
+                let (start, accept) = (new_state(state), new_state(state));
+                self.add_transition(start, Label::AnyExcept(ranges.clone()), accept);
+                (start, accept)
+            }
+            Ast::StartAnchor | Ast::StartText => {
+                let (start, accept) = (new_state(state), new_state(state));
+                self.add_transition(start, Label::Anchor(Anchor::Start), accept);
+                (start, accept)
+            }
+            Ast::EndAnchor | Ast::EndText => {
+                let (start, accept) = (new_state(state), new_state(state));
+                self.add_transition(start, Label::Anchor(Anchor::End), accept);
+                (start, accept)
+            }
+            Ast::Seq(nodes) => {
+                let mut fragments = nodes.iter().map(|node| self.add_fragment(node, state));
+                let Some((start, mut accept)) = fragments.next() else {
+                    // 空の連接は空文字列のみを受理する
+                    let (start, accept) = (new_state(state), new_state(state));
+                    self.add_transition(start, Label::Epsilon, accept);
+                    return (start, accept);
+                };
+                // 各断片の遷移を追加した後に、断片同士をつなぐ
+                let mut links: Vec<(NfaState, NfaState)> = Vec::new();
+                for (next_start, next_accept) in fragments {
+                    links.push((accept, next_start));
+                    accept = next_accept;
+                }
+                for (from, to) in links {
+                    self.add_transition(from, Label::Epsilon, to);
+                }
+                (start, accept)
+            }
+            Ast::Or(_, _) => {
+                // 構造が同じ枝は同じ言語を受理するため、1 つだけ構築する
+                let mut branches: Vec<&Ast> = Vec::new();
+                collect_branches(ast, &mut branches);
+                let mut unique: Vec<&Ast> = Vec::new();
+                for branch in branches {
+                    if !unique.contains(&branch) {
+                        unique.push(branch);
+                    }
+                }
+                if let [branch] = unique[..] {
+                    return self.add_fragment(branch, state);
+                }
+
+                let fragments: Vec<(NfaState, NfaState)> = unique
+                    .into_iter()
+                    .map(|branch| self.add_fragment(branch, state))
+                    .collect();
+                let (start, accept) = (new_state(state), new_state(state));
+                for &(branch_start, _) in &fragments {
+                    self.add_transition(start, Label::Epsilon, branch_start);
+                }
+                for &(_, branch_accept) in &fragments {
+                    self.add_transition(branch_accept, Label::Epsilon, accept);
+                }
+                (start, accept)
+            }
+            Ast::Star { node, .. } => {
+                let fragment = self.add_fragment(node, state);
+                self.add_repeat(fragment, state, true, true)
+            }
+            Ast::Plus { node, .. } => {
+                let fragment = self.add_fragment(node, state);
+                self.add_repeat(fragment, state, false, true)
+            }
+            Ast::Question { node, .. } => {
+                let fragment = self.add_fragment(node, state);
+                self.add_repeat(fragment, state, true, false)
+            }
+            Ast::Repeat { node, min, max, .. } => {
+                // {n,m} は node を n 個並べた後に node? を m - n 個並べ、
+                // {n,} は node を n 個並べた後に node* を並べた連接として構築する
+                let mut nodes: Vec<Ast> = vec![*node.clone(); *min];
+                match max {
+                    Some(max) => {
+                        let question = Ast::Question {
+                            node: node.clone(),
+                            greedy: true,
+                        };
+                        nodes.extend(vec![question; max - min]);
+                    }
+                    None => nodes.push(Ast::Star {
+                        node: node.clone(),
+                        greedy: true,
+                    }),
+                }
+                self.add_fragment(&Ast::Seq(nodes), state)
+            }
+            Ast::Group(node) => self.add_fragment(node, state),
+        }
+    }
+
+    /// 断片を繰り返す遷移を追加し、(開始状態, 受理状態) を返す
+    ///
+    /// skip が true の場合は 0 回の繰り返しを、repeat が true の場合は 2 回以上の繰り返しを許す
+    fn add_repeat(
+        &mut self,
+        (node_start, node_accept): (NfaState, NfaState),
+        state: &mut NfaState,
+        skip: bool,
+        repeat: bool,
+    ) -> (NfaState, NfaState) {
+        let (start, accept) = (new_state(state), new_state(state));
+        self.add_transition(start, Label::Epsilon, node_start);
+        if skip {
+            self.add_transition(start, Label::Epsilon, accept);
+        }
+        if repeat {
+            self.add_transition(node_accept, Label::Epsilon, node_start);
+        }
+        self.add_transition(node_accept, Label::Epsilon, accept);
+        (start, accept)
+    }
+}
+
+/// 入れ子になった選択(`Or`)の枝を、左から順に集める
+fn collect_branches<'a>(ast: &'a Ast, branches: &mut Vec<&'a Ast>) {
+    match ast {
+        Ast::Or(left, right) => {
+            collect_branches(left, branches);
+            collect_branches(right, branches);
+        }
+        _ => branches.push(ast),
+    }
+}
+
+/// c が範囲のいずれかに含まれるかを返す
+fn in_ranges(ranges: &[(char, char)], c: char) -> bool {
+    ranges.iter().any(|&(start, end)| start <= c && c <= end)
+}
+
+/// 文字を消費する遷移のラベルに c が含まれるかを返す
+pub(crate) fn label_matches(label: &Label, c: char) -> bool {
+    match label {
+        Label::Char(l) => *l == c,
+        Label::Class(ranges) => in_ranges(ranges, c),
+        Label::AnyExcept(excluded) => !in_ranges(excluded, c),
+        Label::Epsilon | Label::Anchor(_) => false,
+    }
+}
+
+/// 文字の範囲を `a-z` のように並べた、DOT のラベルとして埋め込める文字列を返す
+fn ranges_label(ranges: &[(char, char)]) -> String {
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                escape_label(start)
+            } else {
+                format!("{}-{}", escape_label(start), escape_label(end))
+            }
+        })
+        .collect()
+}
+
+/// 新しい状態の番号を割り当てる
+fn new_state(state: &mut NfaState) -> NfaState {
+    let new: NfaState = *state;
+    *state += 1;
+    new
+}
+
+// ----- テストコード・試し -----
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        automaton::{
+            dfa::Dfa,
+            nfa::{Anchor, Label, Nfa, NfaState},
+        },
+        parser::parse,
+    };
+
+    /// 式をパースして NFA を構築する
+    fn build(pattern: &str) -> Nfa {
+        let mut state: NfaState = 0;
+        Nfa::from_ast(&parse(pattern).unwrap(), &mut state)
+    }
+
+    /// (遷移元, ラベル, 遷移先) の一覧から NFA を構築する。ラベルが None の場合は ε 遷移とする
+    fn from_edges(accepts: &[NfaState], edges: &[(NfaState, Option<char>, NfaState)]) -> Nfa {
+        let mut nfa = Nfa {
+            start: 0,
+            accepts: accepts.iter().copied().collect(),
+            transitions: Vec::new(),
+        };
+        for &(from, label, to) in edges {
+            nfa.add_transition(from, label.map_or(Label::Epsilon, Label::Char), to);
+        }
+        nfa
+    }
+
+    /// NFA の遷移を (遷移元, ラベル, 遷移先) の一覧に変換する
+    fn edges(nfa: &Nfa) -> Vec<(NfaState, Label, NfaState)> {
+        (0..nfa.transitions.len())
+            .flat_map(|from| {
+                nfa.transitions_from(from)
+                    .iter()
+                    .map(move |(label, to)| (from, label.clone(), *to))
+            })
+            .collect()
+    }
+
+    /// NFA の 1 文字による遷移・ε 遷移を集合に変換する
+    fn transition_set(nfa: &Nfa) -> HashSet<(NfaState, Option<char>, NfaState)> {
+        edges(nfa)
+            .into_iter()
+            .filter_map(|(from, label, to)| match label {
+                Label::Epsilon => Some((from, None, to)),
+                Label::Char(c) => Some((from, Some(c), to)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_next_states() {
+        // 0 -a-> 1, 0 -a-> 2, 0 -ε-> 3, 3 -b-> 1
+        let nfa: Nfa = from_edges(
+            &[1],
+            &[
+                (0, Some('a'), 1),
+                (0, Some('a'), 2),
+                (0, None, 3),
+                (3, Some('b'), 1),
+            ],
+        );
+        assert_eq!(nfa.next_states(0, Some('a')), vec![1, 2]);
+        assert_eq!(nfa.next_states(0, None), vec![3]);
+        // ε 遷移の先の遷移はたどらない
+        assert_eq!(nfa.next_states(0, Some('b')), vec![]);
+        assert_eq!(nfa.next_states(3, Some('b')), vec![1]);
+        assert_eq!(nfa.next_states(1, None), vec![]);
+
+        // ----- "[a-c]|." は範囲・任意の 1 文字による遷移もたどる -----
+        let nfa: Nfa = build("[a-c]|.");
+        let class_start: NfaState = nfa.next_states(nfa.start, None)[0];
+        assert_eq!(nfa.next_states(class_start, Some('b')), vec![1]);
+        assert_eq!(nfa.next_states(class_start, Some('d')), vec![]);
+        let any_start: NfaState = nfa.next_states(nfa.start, None)[1];
+        assert_eq!(nfa.next_states(any_start, Some('d')), vec![3]);
+    }
+
+    #[test]
+    fn test_next_chars() {
+        // 0 -a-> 1, 0 -a-> 2, 0 -ε-> 3, 0 -b-> 3, 0 -ε-> 1
+        let nfa: Nfa = from_edges(
+            &[3],
+            &[
+                (0, Some('a'), 1),
+                (0, Some('a'), 2),
+                (0, None, 3),
+                (0, Some('b'), 3),
+                (0, None, 1),
+            ],
+        );
+        assert_eq!(
+            nfa.next_chars(0),
+            vec![Label::Char('a'), Label::Epsilon, Label::Char('b')]
+        );
+        assert_eq!(nfa.next_chars(3), vec![]);
+
+        // ----- "[a-z]" は範囲による遷移のラベルを返す -----
+        let nfa: Nfa = build("[a-z]");
+        assert_eq!(nfa.next_chars(0), vec![Label::Class(vec![('a', 'z')])]);
+        assert_eq!(nfa.next_chars(1), vec![]);
+
+        // ----- "^." はアンカー・任意の 1 文字による遷移のラベルを返す -----
+        let nfa: Nfa = build("^.");
+        assert_eq!(nfa.next_chars(0), vec![Label::Anchor(Anchor::Start)]);
+        assert_eq!(nfa.next_chars(2), vec![Label::AnyExcept(vec![])]);
+    }
+
+    #[test]
+    fn test_epsilon_closure() {
+        // 0 -ε-> 1 -ε-> 2 -ε-> 3, 2 -a-> 4, 4 -ε-> 5, 3 -ε-> 1
+        let nfa: Nfa = from_edges(
+            &[5],
+            &[
+                (0, None, 1),
+                (1, None, 2),
+                (2, None, 3),
+                (2, Some('a'), 4),
+                (4, None, 5),
+                (3, None, 1),
+            ],
+        );
+        assert_eq!(nfa.epsilon_closure(&[0]), HashSet::from([0, 1, 2, 3]));
+        // 循環する ε 遷移があっても停止する
+        assert_eq!(nfa.epsilon_closure(&[3]), HashSet::from([1, 2, 3]));
+        assert_eq!(
+            nfa.epsilon_closure(&[0, 4]),
+            HashSet::from([0, 1, 2, 3, 4, 5])
+        );
+        assert_eq!(nfa.epsilon_closure(&[5]), HashSet::from([5]));
+        assert_eq!(nfa.epsilon_closure(&[]), HashSet::new());
+
+        // ----- "$^a" は指定したアンカーによる遷移のみを通る -----
+        let nfa: Nfa = build("$^a");
+        assert_eq!(nfa.epsilon_closure(&[0]), HashSet::from([0]));
+        assert_eq!(
+            nfa.closure_with_anchors(&[0], &[Anchor::End]),
+            HashSet::from([0, 1, 2])
+        );
+        assert_eq!(
+            nfa.closure_with_anchors(&[0], &[Anchor::Start, Anchor::End]),
+            HashSet::from([0, 1, 2, 3, 4])
+        );
+    }
+
+    /// ε 遷移と文字による遷移のみを持つ NFA で、input 全体を受理するかを返す
+    fn accepts(nfa: &Nfa, input: &str) -> bool {
+        let mut states: HashSet<NfaState> = nfa.epsilon_closure(&[nfa.start]);
+        for c in input.chars() {
+            let next: Vec<NfaState> = states
+                .iter()
+                .flat_map(|&s| nfa.next_states(s, Some(c)))
+                .collect();
+            states = nfa.epsilon_closure(&next);
+        }
+        states.iter().any(|s| nfa.accepts.contains(s))
+    }
+
+    #[test]
+    fn test_remove_epsilon() {
+        // ----- "a*b" が入力されたケース -----
+        let nfa: Nfa = build("a*b").remove_epsilon();
+        assert!(
+            edges(&nfa)
+                .iter()
+                .all(|(_, label, _)| *label != Label::Epsilon)
+        );
+        assert!(accepts(&nfa, "b"));
+        assert!(accepts(&nfa, "ab"));
+        assert!(accepts(&nfa, "aab"));
+        assert!(!accepts(&nfa, ""));
+        assert!(!accepts(&nfa, "a"));
+        assert!(!accepts(&nfa, "ba"));
+
+        // ----- "(ab)?" は開始状態が受理状態になる -----
+        let nfa: Nfa = build("(ab)?").remove_epsilon();
+        assert!(nfa.accepts.contains(&nfa.start));
+        assert!(accepts(&nfa, "ab"));
+        assert!(!accepts(&nfa, "a"));
+
+        // ----- "^a|b$" はアンカーによる遷移を残す -----
+        let nfa: Nfa = build("^a|b$").remove_epsilon();
+        let anchors: Vec<Label> = edges(&nfa)
+            .into_iter()
+            .filter_map(|(_, label, _)| matches!(label, Label::Anchor(_)).then_some(label))
+            .collect();
+        assert_eq!(
+            anchors,
+            vec![Label::Anchor(Anchor::End), Label::Anchor(Anchor::Start)]
+        );
+        assert!(
+            edges(&nfa)
+                .iter()
+                .all(|(_, label, _)| *label != Label::Epsilon)
+        );
+        // 残したアンカーにより、元の NFA と同じ文字列を受理する
+        let dfa: Dfa = Dfa::from_nfa(&nfa);
+        assert!(dfa.accepts("a"));
+        assert!(dfa.accepts("b"));
+        assert!(!dfa.accepts("ab"));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        // ----- "a|b" が入力されたケース -----
+        let dot: String = build("a|b").to_dot();
+        assert!(dot.starts_with("digraph nfa {"));
+        assert!(dot.contains("start -> 4;"));
+        assert!(dot.contains("5 [shape=doublecircle];"));
+        assert!(dot.contains("0 -> 1 [label=\"a\"];"));
+        assert!(dot.contains("2 -> 3 [label=\"b\"];"));
+        assert!(dot.contains("4 -> 0 [label=\"ε\"];"));
+        assert!(dot.contains("4 -> 2 [label=\"ε\"];"));
+        assert_eq!(dot.matches("[label=\"ε\"]").count(), 4);
+
+        // ----- "^.\D$" が入力されたケース -----
+        let dot: String = build("^.\\D$").to_dot();
+        assert!(dot.contains("0 -> 1 [label=\"^\"];"));
+        assert!(dot.contains("2 -> 3 [label=\".\"];"));
+        assert!(dot.contains("4 -> 5 [label=\"[^0-9]\"];"));
+        assert!(dot.contains("6 -> 7 [label=\"$\"];"));
+
+        // ----- "[a-c\n]" が入力されたケース -----
+        let dot: String = build("[a-c\\n]").to_dot();
+        assert!(dot.contains("0 -> 1 [label=\"[a-c\\\\n]\"];"));
+    }
+
+    #[test]
+    fn test_from_ast_seq() {
+        // ----- "ab" が入力されたケース -----
+        let nfa: Nfa = build("ab");
+        let expect: HashSet<(NfaState, Option<char>, NfaState)> =
+            HashSet::from([(0, Some('a'), 1), (1, None, 2), (2, Some('b'), 3)]);
+        assert_eq!(transition_set(&nfa), expect);
+        assert_eq!(nfa.start, 0);
+        assert_eq!(nfa.accepts, HashSet::from([3]));
+    }
+
+    #[test]
+    fn test_from_ast_or() {
+        // ----- "a|b" が入力されたケース -----
+        let nfa: Nfa = build("a|b");
+        let expect: HashSet<(NfaState, Option<char>, NfaState)> = HashSet::from([
+            (0, Some('a'), 1),
+            (2, Some('b'), 3),
+            (4, None, 0),
+            (4, None, 2),
+            (1, None, 5),
+            (3, None, 5),
+        ]);
+        assert_eq!(transition_set(&nfa), expect);
+        assert_eq!(nfa.start, 4);
+        assert_eq!(nfa.accepts, HashSet::from([5]));
+    }
+
+    #[test]
+    fn test_from_ast_dedup_or() {
+        // ----- "abc|abc" は "abc" と同じ状態数になる -----
+        let mut abc_states: NfaState = 0;
+        let abc: Nfa = Nfa::from_ast(&parse("abc").unwrap(), &mut abc_states);
+        let mut dup_states: NfaState = 0;
+        let dup: Nfa = Nfa::from_ast(&parse("abc|abc").unwrap(), &mut dup_states);
+        assert_eq!(dup_states, abc_states);
+        assert_eq!(dup, abc);
+        assert!(accepts(&dup, "abc"));
+        assert!(!accepts(&dup, "abcabc"));
+
+        // ----- "a|b|a" は重複した枝のみを取り除く -----
+        let nfa: Nfa = build("a|b|a");
+        let expect: HashSet<(NfaState, Option<char>, NfaState)> = HashSet::from([
+            (0, Some('a'), 1),
+            (2, Some('b'), 3),
+            (4, None, 0),
+            (4, None, 2),
+            (1, None, 5),
+            (3, None, 5),
+        ]);
+        assert_eq!(transition_set(&nfa), expect);
+        assert!(accepts(&nfa, "a"));
+        assert!(accepts(&nfa, "b"));
+    }
+
+    #[test]
+    fn test_from_ast_star() {
+        // ----- "a*" が入力されたケース -----
+        let nfa: Nfa = build("a*");
+        let expect: HashSet<(NfaState, Option<char>, NfaState)> = HashSet::from([
+            (0, Some('a'), 1),
+            (2, None, 0),
+            (2, None, 3),
+            (1, None, 0),
+            (1, None, 3),
+        ]);
+        assert_eq!(transition_set(&nfa), expect);
+        assert_eq!(nfa.start, 2);
+        assert_eq!(nfa.accepts, HashSet::from([3]));
+    }
+
+    #[test]
+    fn test_from_ast_other_nodes() {
+        // ----- "[a-cb]" が入力されたケース -----
+        let nfa: Nfa = build("[a-cb]");
+        let expect: Vec<(NfaState, Label, NfaState)> =
+            vec![(0, Label::Class(vec![('a', 'c'), ('b', 'b')]), 1)];
+        assert_eq!(edges(&nfa), expect);
+
+        // ----- "^.\D$" が入力されたケース -----
+        let nfa: Nfa = build("^.\\D$");
+        let expect: Vec<(NfaState, Label, NfaState)> = vec![
+            (0, Label::Anchor(Anchor::Start), 1),
+            (2, Label::AnyExcept(vec![]), 3),
+            (4, Label::AnyExcept(vec![('0', '9')]), 5),
+            (6, Label::Anchor(Anchor::End), 7),
+        ];
+        let actual: Vec<(NfaState, Label, NfaState)> = edges(&nfa)
+            .into_iter()
+            .filter(|(_, label, _)| *label != Label::Epsilon)
+            .collect();
+        assert_eq!(actual, expect);
+
+        // ----- "a{2,3}" が入力されたケース -----
+        let nfa: Nfa = build("a{2,3}");
+        let a_count: usize = edges(&nfa)
+            .iter()
+            .filter(|(_, label, _)| *label == Label::Char('a'))
+            .count();
+        assert_eq!(a_count, 3);
+    }
+}
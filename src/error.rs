@@ -13,8 +13,28 @@ pub enum ParseError {
     InvalidRightParen(usize),
     #[error("ParseError: no previous expression : position = {0}")]
     NoPrev(usize),
+    #[error("ParseError: trailing backslash : position = {0}")]
+    TrailingBackslash(usize),
     #[error("ParseError: no right parenthesis")]
     NoRightParen,
+    #[error("ParseError: invalid right bracket : position = {0}")]
+    InvalidRightBracket(usize),
+    #[error("ParseError: no right bracket")]
+    NoRightBracket,
+    #[error("ParseError: invalid class range : position = {0}")]
+    InvalidClassRange(usize),
+    #[error("ParseError: invalid repeat : position = {0}")]
+    InvalidRepeat(usize),
+    #[error("ParseError: invalid repeat range (min > max) : position = {0}")]
+    InvalidRepeatRange(usize),
+    #[error("ParseError: repeat count too large : position = {0}")]
+    RepeatTooLarge(usize),
+    #[error("ParseError: no right brace")]
+    NoRightBrace,
     #[error("ParseError: empty expression")]
     Empty,
+    #[error("ParseError: pattern too complex")]
+    PatternTooComplex,
+    #[error("ParseError: lookaround is not supported by DFA engines : position = {0}")]
+    UnsupportedLookaround(usize),
 }
@@ -0,0 +1,225 @@
+//! 正規表現の式を字句解析するための型
+//! 式を先頭から読み進め、特殊文字・エスケープ・通常の文字をトークンに変換する。
+//! "a\*|b" が入力された場合、以下のトークンに変換する
+//!
+//! ```text
+//! Character(a), Escape(*), UnionOperator, Character(b), EndOfFile
+//! ```
+
+use std::{fmt, str::Chars};
+
+/// トークンの型
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Character(char),   // 文字
+    Escape(char),      // エスケープされた文字(\ の次の文字を保持する)
+    UnionOperator,     // |
+    StarOperator,      // *
+    PlusOperator,      // +
+    QuestionOperator,  // ?
+    AnyChar,           // .
+    LeftParen,         // (
+    RightParen,        // )
+    LeftBracket,       // [
+    RightBracket,      // ]
+    LeftBrace,         // {
+    StartAnchor,       // ^
+    EndAnchor,         // $
+    TrailingBackslash, // 式の末尾にある、エスケープする文字がない \
+    EndOfFile,         // 式の終わり
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Character(c) => write!(f, "{c}"),
+            Token::Escape(c) => write!(f, "\\{c}"),
+            Token::UnionOperator => write!(f, "|"),
+            Token::StarOperator => write!(f, "*"),
+            Token::PlusOperator => write!(f, "+"),
+            Token::QuestionOperator => write!(f, "?"),
+            Token::AnyChar => write!(f, "."),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBracket => write!(f, "["),
+            Token::RightBracket => write!(f, "]"),
+            Token::LeftBrace => write!(f, "{{"),
+            Token::StartAnchor => write!(f, "^"),
+            Token::EndAnchor => write!(f, "$"),
+            Token::TrailingBackslash => write!(f, "\\"),
+            Token::EndOfFile => write!(f, "EOF"),
+        }
+    }
+}
+
+/// 字句解析器
+///
+/// pos は次に読む文字の位置(バイト単位)を表す
+pub struct Lexer<'a> {
+    chars: Chars<'a>,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(pattern: &'a str) -> Self {
+        Lexer {
+            chars: pattern.chars(),
+            pos: 0,
+        }
+    }
+
+    /// 次に読む文字の位置を返す
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// まだ読んでいない部分の文字列を返す
+    pub fn remaining(&self) -> &'a str {
+        self.chars.as_str()
+    }
+
+    /// 特殊文字として解釈せずに、1 文字読み進める
+    ///
+    /// 文字クラスや回数指定の中身のように、トークンの規則が異なる部分を読むために使う
+    pub fn next_char(&mut self) -> Option<char> {
+        let c: char = self.chars.next()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// 次のトークンと、その開始位置の組を返す
+    ///
+    /// エスケープされた文字のトークンは、`\` の位置を開始位置とする。
+    /// 末尾の `\` は、エスケープする文字がないため `Token::TrailingBackslash` を返す
+    pub fn scan(&mut self) -> (Token, usize) {
+        let pos: usize = self.pos;
+        let Some(c) = self.next_char() else {
+            return (Token::EndOfFile, pos);
+        };
+
+        let token: Token = match c {
+            '\\' => match self.next_char() {
+                Some(escaped) => Token::Escape(escaped),
+                None => Token::TrailingBackslash,
+            },
+            '|' => Token::UnionOperator,
+            '*' => Token::StarOperator,
+            '+' => Token::PlusOperator,
+            '?' => Token::QuestionOperator,
+            '.' => Token::AnyChar,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '{' => Token::LeftBrace,
+            '^' => Token::StartAnchor,
+            '$' => Token::EndAnchor,
+            _ => Token::Character(c),
+        };
+        (token, pos)
+    }
+}
+
+// ----- テストコード・試し -----
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::{Lexer, Token};
+
+    #[test]
+    fn test_scan() {
+        let mut lexer = Lexer::new("a(b|c)*");
+        assert_eq!(lexer.scan().0, Token::Character('a'));
+        assert_eq!(lexer.scan().0, Token::LeftParen);
+        assert_eq!(lexer.scan().0, Token::Character('b'));
+        assert_eq!(lexer.scan().0, Token::UnionOperator);
+        assert_eq!(lexer.scan().0, Token::Character('c'));
+        assert_eq!(lexer.scan().0, Token::RightParen);
+        assert_eq!(lexer.scan().0, Token::StarOperator);
+        assert_eq!(lexer.scan().0, Token::EndOfFile);
+        // 末尾に達した後も EndOfFile を返し続ける
+        assert_eq!(lexer.scan().0, Token::EndOfFile);
+    }
+
+    #[test]
+    fn test_scan_operators() {
+        // ----- "a+b?." が入力されたケース -----
+        let mut lexer = Lexer::new("a+b?.");
+        assert_eq!(lexer.scan().0, Token::Character('a'));
+        assert_eq!(lexer.scan().0, Token::PlusOperator);
+        assert_eq!(lexer.scan().0, Token::Character('b'));
+        assert_eq!(lexer.scan().0, Token::QuestionOperator);
+        assert_eq!(lexer.scan().0, Token::AnyChar);
+        assert_eq!(lexer.scan().0, Token::EndOfFile);
+    }
+
+    #[test]
+    fn test_scan_escape() {
+        // ----- "a\*b" が入力されたケース -----
+        let mut lexer = Lexer::new("a\\*b");
+        assert_eq!(lexer.scan().0, Token::Character('a'));
+        assert_eq!(lexer.scan().0, Token::Escape('*'));
+        assert_eq!(lexer.scan().0, Token::Character('b'));
+        assert_eq!(lexer.scan().0, Token::EndOfFile);
+
+        // ----- "a\" のように末尾が \ のケース -----
+        let mut lexer = Lexer::new("a\\");
+        assert_eq!(lexer.scan().0, Token::Character('a'));
+        assert_eq!(lexer.scan().0, Token::TrailingBackslash);
+        assert_eq!(lexer.scan().0, Token::EndOfFile);
+    }
+
+    #[test]
+    fn test_pos_and_remaining() {
+        let mut lexer = Lexer::new("a\\|[bc]");
+        assert_eq!(lexer.scan().0, Token::Character('a'));
+        assert_eq!(lexer.pos(), 1);
+        assert_eq!(lexer.scan().0, Token::Escape('|'));
+        assert_eq!(lexer.pos(), 3);
+        assert_eq!(lexer.scan().0, Token::LeftBracket);
+        assert_eq!(lexer.remaining(), "bc]");
+
+        // next_char は特殊文字として解釈しない
+        assert_eq!(lexer.next_char(), Some('b'));
+        assert_eq!(lexer.next_char(), Some('c'));
+        assert_eq!(lexer.next_char(), Some(']'));
+        assert_eq!(lexer.next_char(), None);
+        assert_eq!(lexer.pos(), 7);
+    }
+
+    #[test]
+    fn test_scan_position() {
+        // ----- "a\|b" が入力されたケース -----
+        let mut lexer = Lexer::new("a\\|b");
+        assert_eq!(lexer.scan(), (Token::Character('a'), 0));
+        // エスケープは \ の位置を返す
+        assert_eq!(lexer.scan(), (Token::Escape('|'), 1));
+        assert_eq!(lexer.scan(), (Token::Character('b'), 3));
+        assert_eq!(lexer.scan(), (Token::EndOfFile, 4));
+
+        // ----- "あ|b" は位置をバイト単位で返す -----
+        let mut lexer = Lexer::new("あ|b");
+        assert_eq!(lexer.scan(), (Token::Character('あ'), 0));
+        assert_eq!(lexer.scan(), (Token::UnionOperator, 3));
+        assert_eq!(lexer.scan(), (Token::Character('b'), 4));
+        assert_eq!(lexer.pos(), 5);
+    }
+
+    #[test]
+    fn test_token_display() {
+        let tokens: Vec<String> = [
+            Token::Character('a'),
+            Token::Escape('*'),
+            Token::UnionOperator,
+            Token::PlusOperator,
+            Token::QuestionOperator,
+            Token::AnyChar,
+            Token::LeftBrace,
+            Token::EndOfFile,
+        ]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+        assert_eq!(tokens, vec!["a", "\\*", "|", "+", "?", ".", "{", "EOF"]);
+    }
+}
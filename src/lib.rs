@@ -1,3 +1,6 @@
-mod automaton;
+pub mod automaton;
 mod error;
+pub mod lexer;
 pub mod parser;
+pub mod prelude;
+pub mod regex;
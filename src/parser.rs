@@ -22,28 +22,377 @@
 //! )
 //! ```
 
-use std::mem::take;
+use std::{
+    mem::take,
+    num::{IntErrorKind, ParseIntError},
+};
 
-use crate::error::ParseError;
+use crate::{
+    error::ParseError,
+    lexer::{Lexer, Token},
+};
 
 // エスケープ文字を定義
-const ESCAPE_CHARS: [char; 5] = ['\\', '(', ')', '|', '*'];
+const ESCAPE_CHARS: [char; 14] = [
+    '\\', '(', ')', '|', '*', '+', '?', '.', '[', ']', '{', '}', '^', '$',
+];
+
+// \d, \w, \s が表す文字の範囲を定義
+const DIGIT_RANGES: [(char, char); 1] = [('0', '9')];
+const WORD_RANGES: [(char, char); 4] = [('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE_RANGES: [(char, char); 2] = [('\t', '\r'), (' ', ' ')];
+
+// 先読み・後読みを表すグループの開始部分を定義
+const LOOKAROUND_PREFIXES: [&str; 4] = ["?=", "?!", "?<=", "?<!"];
+
+// 回数指定で指定できる回数の上限。NFA は回数分だけ対象を複製するため、大きな回数を拒否する
+const MAX_REPEAT: usize = 1000;
+
+// 回数指定を展開した後のノード数の上限。入れ子の回数指定は複製の数が掛け合わされるため、全体でも制限する
+const MAX_EXPANDED_NODES: usize = 10_000;
 
 /// Ast の型
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Ast {
-    Char(char),             // 文字
-    Star(Box<Ast>),         // 繰り返し(*)
-    Or(Box<Ast>, Box<Ast>), // 選択(|)
-    Seq(Vec<Ast>),          // 連接
+    Char(char),                  // 文字
+    AnyChar,                     // 任意の 1 文字(.)
+    Or(Box<Ast>, Box<Ast>),      // 選択(|)
+    Seq(Vec<Ast>),               // 連接
+    Class(Vec<(char, char)>),    // 文字クラス([a-z])。範囲を (開始, 終了) の組で保持する
+    NegClass(Vec<(char, char)>), // 否定の文字クラス(\D など)
+    StartAnchor,                 // 先頭(^)
+    EndAnchor,                   // 末尾($)
+    StartText,                   // 入力の先頭(\A)
+    EndText,                     // 入力の末尾(\z)
+    Group(Box<Ast>),             // 量指定子の対象を明示するグループ
+    // 繰り返し(*)。greedy が false の場合は非貪欲(*?)
+    Star {
+        node: Box<Ast>,
+        greedy: bool,
+    },
+    // 1 回以上の繰り返し(+)。greedy が false の場合は非貪欲(+?)
+    Plus {
+        node: Box<Ast>,
+        greedy: bool,
+    },
+    // 0 回または 1 回(?)。greedy が false の場合は非貪欲(??)
+    Question {
+        node: Box<Ast>,
+        greedy: bool,
+    },
+    // 回数指定の繰り返し({n}, {n,}, {n,m})。max が None の場合は上限なし
+    Repeat {
+        node: Box<Ast>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+impl Ast {
+    /// 1 文字のみの選択(`a|b|c`)を文字クラスに変換する
+    ///
+    /// `Or` の枝がすべて 1 文字の場合、`Class` にまとめることで、
+    /// 生成するオートマトンの状態数を減らす。受理する言語は変わらない。
+    pub fn coalesce_char_alternatives(self) -> Ast {
+        match self {
+            Ast::Or(left, right) => {
+                let left: Ast = left.coalesce_char_alternatives();
+                let right: Ast = right.coalesce_char_alternatives();
+                match (left.single_ranges(), right.single_ranges()) {
+                    (Some(mut ranges), Some(right_ranges)) => {
+                        for range in right_ranges {
+                            if !ranges.contains(&range) {
+                                ranges.push(range);
+                            }
+                        }
+                        Ast::Class(ranges)
+                    }
+                    _ => Ast::Or(Box::new(left), Box::new(right)),
+                }
+            }
+            Ast::Star { node, greedy } => Ast::Star {
+                node: Box::new(node.coalesce_char_alternatives()),
+                greedy,
+            },
+            Ast::Plus { node, greedy } => Ast::Plus {
+                node: Box::new(node.coalesce_char_alternatives()),
+                greedy,
+            },
+            Ast::Question { node, greedy } => Ast::Question {
+                node: Box::new(node.coalesce_char_alternatives()),
+                greedy,
+            },
+            Ast::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => Ast::Repeat {
+                node: Box::new(node.coalesce_char_alternatives()),
+                min,
+                max,
+                greedy,
+            },
+            Ast::Group(ast) => Ast::Group(Box::new(ast.coalesce_char_alternatives())),
+            Ast::Seq(seq) => Ast::Seq(
+                seq.into_iter()
+                    .map(Ast::coalesce_char_alternatives)
+                    .collect(),
+            ),
+            ast => ast,
+        }
+    }
+
+    /// 意味的に等しい Ast かどうかを判定する
+    ///
+    /// 要素が 1 つの `Seq` やネストした `Seq`、`Group` の有無は区別せずに比較する。
+    /// そのため `Seq([Char(a)])` と `Char(a)` は等しいと判定される。
+    pub fn semantically_eq(&self, other: &Ast) -> bool {
+        self.normalize() == other.normalize()
+    }
+
+    /// 比較のため、`Seq` の平坦化と `Group` の除去を行った Ast を生成する
+    fn normalize(&self) -> Ast {
+        match self {
+            Ast::Seq(seq) => {
+                let mut flat: Vec<Ast> = Vec::new();
+                for ast in seq {
+                    match ast.normalize() {
+                        Ast::Seq(inner) => flat.extend(inner),
+                        ast => flat.push(ast),
+                    }
+                }
+                if flat.len() == 1 {
+                    flat.pop().unwrap()
+                } else {
+                    Ast::Seq(flat)
+                }
+            }
+            Ast::Star { node, greedy } => Ast::Star {
+                node: Box::new(node.normalize()),
+                greedy: *greedy,
+            },
+            Ast::Plus { node, greedy } => Ast::Plus {
+                node: Box::new(node.normalize()),
+                greedy: *greedy,
+            },
+            Ast::Question { node, greedy } => Ast::Question {
+                node: Box::new(node.normalize()),
+                greedy: *greedy,
+            },
+            Ast::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => Ast::Repeat {
+                node: Box::new(node.normalize()),
+                min: *min,
+                max: *max,
+                greedy: *greedy,
+            },
+            Ast::Or(left, right) => {
+                Ast::Or(Box::new(left.normalize()), Box::new(right.normalize()))
+            }
+            Ast::Group(ast) => ast.normalize(),
+            ast => ast.clone(),
+        }
+    }
+
+    /// 回数指定 `{n,m}` を対象の複製に展開した場合のノード数を返す
+    ///
+    /// NFA は `{n}` と `{n,m}` を対象の n 個・m 個の複製として、`{n,}` を n + 1 個の複製として構築する
+    fn expanded_size(&self) -> usize {
+        match self {
+            Ast::Seq(seq) => seq
+                .iter()
+                .fold(1, |size, ast| size.saturating_add(ast.expanded_size())),
+            Ast::Or(left, right) => left
+                .expanded_size()
+                .saturating_add(right.expanded_size())
+                .saturating_add(1),
+            Ast::Star { node, .. }
+            | Ast::Plus { node, .. }
+            | Ast::Question { node, .. }
+            | Ast::Group(node) => node.expanded_size().saturating_add(1),
+            Ast::Repeat { node, min, max, .. } => {
+                let copies: usize = max.unwrap_or(min.saturating_add(1));
+                node.expanded_size()
+                    .saturating_mul(copies)
+                    .saturating_add(1)
+            }
+            _ => 1,
+        }
+    }
+
+    /// 1 文字にマッチする Ast の場合、マッチする文字の範囲の一覧を返す
+    fn single_ranges(&self) -> Option<Vec<(char, char)>> {
+        match self {
+            Ast::Char(c) => Some(vec![(*c, *c)]),
+            Ast::Class(ranges) => Some(ranges.clone()),
+            Ast::Seq(seq) if seq.len() == 1 => seq[0].single_ranges(),
+            _ => None,
+        }
+    }
 }
 
 /// エスケープ文字から Ast を生成
+///
+/// `\n`, `\t`, `\r` は改行・タブ・復帰文字を表す。
+/// `\d`, `\w`, `\s` は数字・単語構成文字・空白文字のクラスを、大文字はその否定を表す。
+/// `\A`, `\z` は行を意識しない、入力全体の先頭・末尾を表す
 fn parse_escape(pos: usize, c: char) -> Result<Ast, ParseError> {
-    if ESCAPE_CHARS.contains(&c) {
-        Ok(Ast::Char(c))
+    match c {
+        'n' => Ok(Ast::Char('\n')),
+        't' => Ok(Ast::Char('\t')),
+        'r' => Ok(Ast::Char('\r')),
+        'd' => Ok(Ast::Class(DIGIT_RANGES.to_vec())),
+        'w' => Ok(Ast::Class(WORD_RANGES.to_vec())),
+        's' => Ok(Ast::Class(SPACE_RANGES.to_vec())),
+        'D' => Ok(Ast::NegClass(DIGIT_RANGES.to_vec())),
+        'W' => Ok(Ast::NegClass(WORD_RANGES.to_vec())),
+        'S' => Ok(Ast::NegClass(SPACE_RANGES.to_vec())),
+        'A' => Ok(Ast::StartText),
+        'z' => Ok(Ast::EndText),
+        _ if ESCAPE_CHARS.contains(&c) => Ok(Ast::Char(c)),
+        _ => Err(ParseError::InvalidEscape(pos, c)),
+    }
+}
+
+/// 文字クラス `[...]` の中で、エスケープされた文字を返す
+///
+/// `\n`, `\t`, `\r` は改行・タブ・復帰文字を表す。
+/// 特殊文字と `-` はその文字自身を表し、それ以外はエラーとする。
+fn parse_class_escape(pos: usize, c: char) -> Result<char, ParseError> {
+    match c {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '-' => Ok('-'),
+        _ if ESCAPE_CHARS.contains(&c) => Ok(c),
+        _ => Err(ParseError::InvalidEscape(pos, c)),
+    }
+}
+
+/// 文字クラス `[...]` の中身から Ast を生成
+///
+/// `[` の直後から読み進め、対応する `]` までの文字を `Ast::Class` にまとめる。
+/// 先頭が `^` の場合は否定の文字クラスとして `Ast::NegClass` にまとめる。
+/// クラスの中では `.` や `*` などは文字として扱い、`\` によるエスケープのみ解釈する。
+/// `a-z` は範囲として扱い、先頭・末尾の `-` とエスケープした `\-` は文字 `-` として扱う。
+/// 先頭(`^` の直後を含む)の `]` は、空のクラスとせずに文字 `]` として扱う。
+/// `]` が見つからない場合、範囲の開始が終了より大きい場合はエラーを返す。
+fn parse_class(lexer: &mut Lexer) -> Result<Ast, ParseError> {
+    // (位置, 文字, エスケープされているか) の組を保持する
+    let mut body: Vec<(usize, char, bool)> = Vec::new();
+    let mut is_closed: bool = false;
+    let mut is_negated: bool = false;
+    let mut is_first: bool = true;
+    loop {
+        let pos: usize = lexer.pos();
+        let Some(c) = lexer.next_char() else {
+            break;
+        };
+        match c {
+            '^' if is_first && !is_negated => {
+                is_negated = true;
+                continue;
+            }
+            ']' if !is_first => {
+                is_closed = true;
+                break;
+            }
+            '\\' => {
+                // クラスの末尾でエスケープが終わっている場合は、閉じカッコが足りないエラー
+                let escaped: char = lexer.next_char().ok_or(ParseError::NoRightBracket)?;
+                body.push((pos, parse_class_escape(pos + 1, escaped)?, true));
+            }
+            _ => body.push((pos, c, false)),
+        }
+        is_first = false;
+    }
+    if !is_closed {
+        return Err(ParseError::NoRightBracket);
+    }
+
+    let mut ranges: Vec<(char, char)> = Vec::new();
+    let mut i: usize = 0;
+    while i < body.len() {
+        let (pos, start, _) = body[i];
+        // "x-y" の形で、'-' が末尾でない場合は範囲として扱う
+        if i + 2 < body.len() && body[i + 1].1 == '-' && !body[i + 1].2 {
+            let end: char = body[i + 2].1;
+            if start > end {
+                return Err(ParseError::InvalidClassRange(pos));
+            }
+            ranges.push((start, end));
+            i += 3;
+        } else {
+            ranges.push((start, start));
+            i += 1;
+        }
+    }
+    if is_negated {
+        Ok(Ast::NegClass(ranges))
+    } else {
+        Ok(Ast::Class(ranges))
+    }
+}
+
+/// 回数指定 `{n}`, `{n,}`, `{n,m}` の中身をパースし、(最小回数, 最大回数) を返す
+///
+/// `{` の直後から `}` までを読み進める。pos は `{` の位置。
+/// 回数が `MAX_REPEAT` を超える場合(`usize` に収まらない場合を含む)は `ParseError::RepeatTooLarge` を返す。
+fn parse_repeat(pos: usize, lexer: &mut Lexer) -> Result<(usize, Option<usize>), ParseError> {
+    let mut body: String = String::new();
+    let mut is_closed: bool = false;
+    while let Some(c) = lexer.next_char() {
+        if c == '}' {
+            is_closed = true;
+            break;
+        }
+        body.push(c);
+    }
+    if !is_closed {
+        return Err(ParseError::NoRightBrace);
+    }
+
+    let parse_count = |s: &str| -> Result<usize, ParseError> {
+        s.parse().map_err(|e: ParseIntError| match e.kind() {
+            IntErrorKind::PosOverflow => ParseError::RepeatTooLarge(pos),
+            _ => ParseError::InvalidRepeat(pos),
+        })
+    };
+    let (min, max) = match body.split_once(',') {
+        None => {
+            let n: usize = parse_count(&body)?;
+            (n, Some(n))
+        }
+        Some((min, "")) => (parse_count(min)?, None),
+        Some((min, max)) => (parse_count(min)?, Some(parse_count(max)?)),
+    };
+
+    if let Some(max) = max
+        && min > max
+    {
+        return Err(ParseError::InvalidRepeatRange(pos));
+    }
+    if min.max(max.unwrap_or(0)) > MAX_REPEAT {
+        return Err(ParseError::RepeatTooLarge(pos));
+    }
+    Ok((min, max))
+}
+
+/// 量指定子の直後を確認し、貪欲(greedy)かどうかを返す
+///
+/// 量指定子の直後の '?' は非貪欲を表すため、読み進めて false を返す
+fn scan_greedy(lexer: &mut Lexer) -> bool {
+    if lexer.remaining().starts_with('?') {
+        lexer.scan();
+        false
     } else {
-        Err(ParseError::InvalidEscape(pos, c))
+        true
     }
 }
 
@@ -74,50 +423,279 @@ fn fold_or(mut seq_or: Vec<Ast>) -> Option<Ast> {
     }
 }
 
+/// 生成した Ast のノード数を数えるためのカウンタ
+///
+/// ノード数が上限を超えた時点でエラーを返す
+struct NodeCounter {
+    count: usize,
+    max_nodes: usize,
+}
+
+impl NodeCounter {
+    /// ノード数を n 加算し、上限を超えた場合はエラーを返す
+    fn add(&mut self, n: usize) -> Result<(), ParseError> {
+        self.count = self.count.saturating_add(n);
+        if self.count > self.max_nodes {
+            Err(ParseError::PatternTooComplex)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// パターン上の範囲
+///
+/// バイト単位の位置で、start 以上 end 未満を表す
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Ast の各ノードに対応する範囲を、Ast と同じ木構造で保持する型
+///
+/// children は Ast の子ノードと同じ順序で並ぶ
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanTree {
+    pub span: Span,
+    pub children: Vec<SpanTree>,
+}
+
+/// パースの挙動を指定するオプション
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// 生成する Ast のノード数の上限
+    pub max_nodes: usize,
+    /// 量指定子の対象を `Ast::Group` で包み、結合の優先順位を明示する
+    pub explicit_grouping: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_nodes: usize::MAX,
+            explicit_grouping: false,
+        }
+    }
+}
+
 /// 式をパースし、Astを生成
 pub fn parse(pattern: &str) -> Result<Ast, ParseError> {
-    let mut seq: Vec<Ast> = Vec::new();
-    let mut seq_or: Vec<Ast> = Vec::new();
-    let mut stack: Vec<(Vec<Ast>, Vec<Ast>)> = Vec::new();
-    let mut is_escape: bool = false;
-
-    for (pos, c) in pattern.chars().enumerate() {
-        if is_escape {
-            is_escape = false;
-            seq.push(parse_escape(pos, c)?);
-            continue;
-        }
+    parse_with_options(pattern, &ParseOptions::default())
+}
 
-        match c {
-            '*' => {
-                let prev_ast = seq.pop().ok_or(ParseError::NoPrev(pos))?;
-                let ast: Ast = Ast::Star(Box::new(prev_ast));
-                seq.push(ast);
-            }
-            '(' => {
-                let prev: Vec<Ast> = take(&mut seq);
-                let prev_or: Vec<Ast> = take(&mut seq_or);
-                stack.push((prev, prev_or));
+/// Ast のノード数の上限を指定して式をパースし、Astを生成
+///
+/// 信頼できない入力に対してメモリ使用量を抑えるため、
+/// 生成したノード数が max_nodes を超えた時点で `ParseError::PatternTooComplex` を返す。
+pub fn parse_with_node_limit(pattern: &str, max_nodes: usize) -> Result<Ast, ParseError> {
+    let options = ParseOptions {
+        max_nodes,
+        ..ParseOptions::default()
+    };
+    parse_with_options(pattern, &options)
+}
+
+/// オプションを指定して式をパースし、Astを生成
+///
+/// `explicit_grouping` が true の場合、"ab*" は以下の Ast に変換され、
+/// `*` が b のみに結合していることが明示される
+/// ```text
+/// Seq(
+///     Char(a),
+///     Star(Group(Char(b)))
+/// )
+/// ```
+pub fn parse_with_options(pattern: &str, options: &ParseOptions) -> Result<Ast, ParseError> {
+    let (ast, _) = parse_spanned(pattern, options)?;
+    Ok(ast)
+}
+
+/// 式をパースし、Ast と各ノードの位置情報を生成
+///
+/// "a(bc)*" が入力された場合、`Star` に対応する範囲は `(bc)*` 全体(1..6)となる。
+pub fn parse_with_spans(pattern: &str) -> Result<(Ast, SpanTree), ParseError> {
+    parse_spanned(pattern, &ParseOptions::default())
+}
+
+/// Ast と、その範囲の組
+type Spanned = (Ast, SpanTree);
+
+/// 子ノードを持たないノードの範囲を生成
+fn leaf(start: usize, end: usize) -> SpanTree {
+    SpanTree {
+        span: Span { start, end },
+        children: Vec::new(),
+    }
+}
+
+/// 連接する Ast から `Ast::Seq` と、その範囲を生成
+///
+/// 空の連接の場合は pos の位置に長さ 0 の範囲を割り当てる
+fn make_seq(seq: Vec<Spanned>, pos: usize) -> Spanned {
+    let start: usize = seq.first().map_or(pos, |(_, t)| t.span.start);
+    let end: usize = seq.last().map_or(pos, |(_, t)| t.span.end);
+    let (asts, children): (Vec<Ast>, Vec<SpanTree>) = seq.into_iter().unzip();
+    (
+        Ast::Seq(asts),
+        SpanTree {
+            span: Span { start, end },
+            children,
+        },
+    )
+}
+
+/// `fold_or` と同じ形で、Ast とその範囲を生成
+fn fold_or_spanned(seq_or: Vec<Spanned>) -> Option<Spanned> {
+    let (asts, mut trees): (Vec<Ast>, Vec<SpanTree>) = seq_or.into_iter().unzip();
+    let ast: Ast = fold_or(asts)?;
+
+    let mut tree: SpanTree = trees.pop().unwrap();
+    trees.reverse();
+    for t in trees {
+        tree = SpanTree {
+            span: Span {
+                start: t.span.start,
+                end: tree.span.end,
+            },
+            children: vec![t, tree],
+        };
+    }
+    Some((ast, tree))
+}
+
+/// パースの本体。Ast と、同じ木構造で各ノードの範囲を生成する
+fn parse_spanned(pattern: &str, options: &ParseOptions) -> Result<(Ast, SpanTree), ParseError> {
+    let mut counter = NodeCounter {
+        count: 0,
+        max_nodes: options.max_nodes,
+    };
+    let mut seq: Vec<Spanned> = Vec::new();
+    let mut seq_or: Vec<Spanned> = Vec::new();
+    let mut stack: Vec<(Vec<Spanned>, Vec<Spanned>, usize)> = Vec::new();
+    let mut lexer = Lexer::new(pattern);
+
+    loop {
+        let (token, pos) = lexer.scan();
+
+        match token {
+            Token::StarOperator
+            | Token::PlusOperator
+            | Token::QuestionOperator
+            | Token::LeftBrace => {
+                let (mut prev_ast, mut prev_tree) = seq.pop().ok_or(ParseError::NoPrev(pos))?;
+                if options.explicit_grouping {
+                    counter.add(1)?;
+                    prev_ast = Ast::Group(Box::new(prev_ast));
+                    prev_tree = SpanTree {
+                        span: prev_tree.span,
+                        children: vec![prev_tree],
+                    };
+                }
+                counter.add(1)?;
+                let node: Box<Ast> = Box::new(prev_ast);
+                let ast: Ast = match token {
+                    Token::StarOperator => Ast::Star {
+                        node,
+                        greedy: scan_greedy(&mut lexer),
+                    },
+                    Token::PlusOperator => Ast::Plus {
+                        node,
+                        greedy: scan_greedy(&mut lexer),
+                    },
+                    Token::QuestionOperator => Ast::Question {
+                        node,
+                        greedy: scan_greedy(&mut lexer),
+                    },
+                    _ => {
+                        let (min, max) = parse_repeat(pos, &mut lexer)?;
+                        let ast = Ast::Repeat {
+                            node,
+                            min,
+                            max,
+                            greedy: scan_greedy(&mut lexer),
+                        };
+                        // 入れ子の回数指定は複製の数が掛け合わされるため、展開後の大きさで制限する
+                        if ast.expanded_size() > MAX_EXPANDED_NODES {
+                            return Err(ParseError::RepeatTooLarge(pos));
+                        }
+                        ast
+                    }
+                };
+                let tree = SpanTree {
+                    span: Span {
+                        start: prev_tree.span.start,
+                        end: lexer.pos(),
+                    },
+                    children: vec![prev_tree],
+                };
+                seq.push((ast, tree));
             }
-            ')' => {
-                let (mut prev, prev_or) = stack.pop().ok_or(ParseError::InvalidRightParen(pos))?;
-                if !seq.is_empty() {
-                    seq_or.push(Ast::Seq(seq));
+            Token::LeftParen => {
+                // DFA では先読み・後読みを扱えないため、専用のエラーを返す
+                let rest: &str = lexer.remaining();
+                if LOOKAROUND_PREFIXES.iter().any(|p| rest.starts_with(p)) {
+                    return Err(ParseError::UnsupportedLookaround(pos));
                 }
+                let prev: Vec<Spanned> = take(&mut seq);
+                let prev_or: Vec<Spanned> = take(&mut seq_or);
+                stack.push((prev, prev_or, pos));
+            }
+            Token::RightParen => {
+                let (mut prev, prev_or, open) =
+                    stack.pop().ok_or(ParseError::InvalidRightParen(pos))?;
+                // "(a|)" や "()" のように中身が空の場合も、空の連接を追加してグループを 1 つのノードにする
+                counter.add(1)?;
+                seq_or.push(make_seq(seq, pos));
 
-                if let Some(ast) = fold_or(seq_or) {
-                    prev.push(ast);
-                }
+                counter.add(seq_or.len() - 1)?;
+                let (ast, mut tree) =
+                    fold_or_spanned(seq_or).expect("seq_or には 1 つ以上の選択肢がある");
+                // グループの範囲には括弧を含める
+                tree.span = Span {
+                    start: open,
+                    end: lexer.pos(),
+                };
+                prev.push((ast, tree));
 
                 seq = prev;
                 seq_or = prev_or;
             }
-            '|' => {
-                let prev: Vec<Ast> = take(&mut seq);
-                seq_or.push(Ast::Seq(prev));
+            Token::UnionOperator => {
+                let prev: Vec<Spanned> = take(&mut seq);
+                counter.add(1)?;
+                seq_or.push(make_seq(prev, pos));
+            }
+            Token::AnyChar => {
+                counter.add(1)?;
+                seq.push((Ast::AnyChar, leaf(pos, lexer.pos())));
+            }
+            Token::StartAnchor => {
+                counter.add(1)?;
+                seq.push((Ast::StartAnchor, leaf(pos, lexer.pos())));
+            }
+            Token::EndAnchor => {
+                counter.add(1)?;
+                seq.push((Ast::EndAnchor, leaf(pos, lexer.pos())));
+            }
+            Token::LeftBracket => {
+                counter.add(1)?;
+                let ast: Ast = parse_class(&mut lexer)?;
+                seq.push((ast, leaf(pos, lexer.pos())));
             }
-            '\\' => is_escape = true,
-            _ => seq.push(Ast::Char(c)),
+            Token::RightBracket => return Err(ParseError::InvalidRightBracket(pos)),
+            Token::Escape(c) => {
+                counter.add(1)?;
+                // エラーの位置は '\' の次の文字の位置とする
+                let ast: Ast = parse_escape(pos + 1, c)?;
+                seq.push((ast, leaf(pos, lexer.pos())));
+            }
+            Token::Character(c) => {
+                counter.add(1)?;
+                seq.push((Ast::Char(c), leaf(pos, lexer.pos())));
+            }
+            Token::TrailingBackslash => return Err(ParseError::TrailingBackslash(pos)),
+            Token::EndOfFile => break,
         };
     }
     // 閉じカッコが足りないエラー
@@ -125,14 +703,16 @@ pub fn parse(pattern: &str) -> Result<Ast, ParseError> {
         return Err(ParseError::NoRightParen);
     }
 
-    // seq が残っている場合、seq_or に追加
-    if !seq.is_empty() {
-        seq_or.push(Ast::Seq(seq));
+    // seq が残っている場合、または "a|" のように `|` の後が空の場合、seq_or に追加
+    if !seq.is_empty() || !seq_or.is_empty() {
+        counter.add(1)?;
+        seq_or.push(make_seq(seq, lexer.pos()));
     }
 
     // 最後に seq_or を fold して、Ast を生成
-    if let Some(ast) = fold_or(seq_or) {
-        Ok(ast)
+    counter.add(seq_or.len().saturating_sub(1))?;
+    if let Some(spanned) = fold_or_spanned(seq_or) {
+        Ok(spanned)
     } else {
         Err(ParseError::Empty)
     }
@@ -142,7 +722,10 @@ pub fn parse(pattern: &str) -> Result<Ast, ParseError> {
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::{Ast, ParseError, fold_or, parse, parse_escape};
+    use crate::parser::{
+        Ast, ParseError, ParseOptions, Span, SpanTree, fold_or, parse, parse_escape,
+        parse_with_node_limit, parse_with_options, parse_with_spans,
+    };
 
     #[test]
     fn test_parse_escape_success() {
@@ -151,6 +734,65 @@ mod tests {
         assert_eq!(actual, expect);
     }
 
+    #[test]
+    fn test_parse_escape_control_char() {
+        // ----- "a\nb" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('a'), Ast::Char('\n'), Ast::Char('b')]);
+        let actual: Ast = parse("a\\nb").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\t\r" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('\t'), Ast::Char('\r')]);
+        let actual: Ast = parse("\\t\\r").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\q" は未定義のエスケープ -----
+        let expect = Err(ParseError::InvalidEscape(1, 'q'));
+        let actual = parse("\\q");
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_escape_shorthand_class() {
+        let digit: Vec<(char, char)> = vec![('0', '9')];
+        let word: Vec<(char, char)> = vec![('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+        let space: Vec<(char, char)> = vec![('\t', '\r'), (' ', ' ')];
+
+        // ----- "\d+" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Plus {
+            node: Box::new(Ast::Class(digit.clone())),
+            greedy: true,
+        }]);
+        let actual: Ast = parse("\\d+").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a\wb" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Class(word.clone()),
+            Ast::Char('b'),
+        ]);
+        let actual: Ast = parse("a\\wb").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\s*" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Star {
+            node: Box::new(Ast::Class(space.clone())),
+            greedy: true,
+        }]);
+        let actual: Ast = parse("\\s*").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\D\W\S" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::NegClass(digit),
+            Ast::NegClass(word),
+            Ast::NegClass(space),
+        ]);
+        let actual: Ast = parse("\\D\\W\\S").unwrap();
+        assert_eq!(actual, expect);
+    }
+
     #[test]
     fn test_parse_escape_failure() {
         let expect = Err(ParseError::InvalidEscape(0, 'a'));
@@ -207,6 +849,40 @@ mod tests {
         let pattern: &str = "abc|def|ghi";
         let actual: Ast = parse(pattern).unwrap();
         assert_eq!(actual, expect);
+        // ----- "a|" は末尾の空の選択肢を残す -----
+        let expect: Ast = Ast::Or(
+            Box::new(Ast::Seq(vec![Ast::Char('a')])),
+            Box::new(Ast::Seq(vec![])),
+        );
+        let actual: Ast = parse("a|").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "(a|)b" はグループの中の末尾の空の選択肢を残す -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Or(
+                Box::new(Ast::Seq(vec![Ast::Char('a')])),
+                Box::new(Ast::Seq(vec![])),
+            ),
+            Ast::Char('b'),
+        ]);
+        let actual: Ast = parse("(a|)b").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a()*" は空のグループを繰り返す -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Star {
+                node: Box::new(Ast::Seq(vec![])),
+                greedy: true,
+            },
+        ]);
+        let actual: Ast = parse("a()*").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "()" は空の連接になる -----
+        let expect: Ast = Ast::Seq(vec![Ast::Seq(vec![])]);
+        let actual: Ast = parse("()").unwrap();
+        assert_eq!(actual, expect);
     }
 
     #[test]
@@ -244,6 +920,432 @@ mod tests {
         assert_eq!(actual, expect);
     }
 
+    #[test]
+    fn test_parse_escape_with_star() {
+        // ----- "\**" が入力されたケース -----
+        // エスケープした '*' に対して、後ろの '*' が繰り返しとして適用される
+        let expect: Ast = Ast::Seq(vec![Ast::Star {
+            node: Box::new(Ast::Char('*')),
+            greedy: true,
+        }]);
+        let pattern: &str = "\\**";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a\**b" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Star {
+                node: Box::new(Ast::Char('*')),
+                greedy: true,
+            },
+            Ast::Char('b'),
+        ]);
+        let pattern: &str = "a\\**b";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_plus() {
+        // ----- "a+" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Plus {
+            node: Box::new(Ast::Char('a')),
+            greedy: true,
+        }]);
+        let pattern: &str = "a+";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "(ab)+" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Plus {
+            node: Box::new(Ast::Seq(vec![Ast::Char('a'), Ast::Char('b')])),
+            greedy: true,
+        }]);
+        let pattern: &str = "(ab)+";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a\+" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('a'), Ast::Char('+')]);
+        let pattern: &str = "a\\+";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_any_char() {
+        // ----- "a.c" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('a'), Ast::AnyChar, Ast::Char('c')]);
+        let pattern: &str = "a.c";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\." が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('.')]);
+        let pattern: &str = "\\.";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a.*" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Star {
+                node: Box::new(Ast::AnyChar),
+                greedy: true,
+            },
+        ]);
+        let pattern: &str = "a.*";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_class() {
+        // ----- "[abc]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('a', 'a'), ('b', 'b'), ('c', 'c')])]);
+        let pattern: &str = "[abc]";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a[xy]b" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Class(vec![('x', 'x'), ('y', 'y')]),
+            Ast::Char('b'),
+        ]);
+        let pattern: &str = "a[xy]b";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[ab]*" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Star {
+            node: Box::new(Ast::Class(vec![('a', 'a'), ('b', 'b')])),
+            greedy: true,
+        }]);
+        let pattern: &str = "[ab]*";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_quantified_class() {
+        // ----- "[a-z]+" はクラス全体を繰り返す -----
+        let expect: Ast = Ast::Seq(vec![Ast::Plus {
+            node: Box::new(Ast::Class(vec![('a', 'z')])),
+            greedy: true,
+        }]);
+        let actual: Ast = parse("[a-z]+").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "x[abc]?" は直前の文字を含めない -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('x'),
+            Ast::Question {
+                node: Box::new(Ast::Class(vec![('a', 'a'), ('b', 'b'), ('c', 'c')])),
+                greedy: true,
+            },
+        ]);
+        let actual: Ast = parse("x[abc]?").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[ab]{2}" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Repeat {
+            node: Box::new(Ast::Class(vec![('a', 'a'), ('b', 'b')])),
+            min: 2,
+            max: Some(2),
+            greedy: true,
+        }]);
+        let actual: Ast = parse("[ab]{2}").unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_class_escape() {
+        // ----- "[\]]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![(']', ']')])]);
+        let actual: Ast = parse("[\\]]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[\]\\]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![(']', ']'), ('\\', '\\')])]);
+        let actual: Ast = parse("[\\]\\\\]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[\n]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('\n', '\n')])]);
+        let actual: Ast = parse("[\\n]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[a\-z]" はエスケープした '-' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('a', 'a'), ('-', '-'), ('z', 'z')])]);
+        let actual: Ast = parse("[a\\-z]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[.*|()]" はクラスの中の特殊文字を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![
+            ('.', '.'),
+            ('*', '*'),
+            ('|', '|'),
+            ('(', '('),
+            (')', ')'),
+        ])]);
+        let actual: Ast = parse("[.*|()]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[a\" はエスケープの途中で終わっているためエラー -----
+        let expect = Err(ParseError::NoRightBracket);
+        let actual = parse("[a\\");
+        assert_eq!(actual, expect);
+
+        // ----- "[\q]" は未定義のエスケープ -----
+        let expect = Err(ParseError::InvalidEscape(2, 'q'));
+        let actual = parse("[\\q]");
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_class_range() {
+        // ----- "[a-c]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('a', 'c')])]);
+        let actual: Ast = parse("[a-c]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[a-cx-z]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('a', 'c'), ('x', 'z')])]);
+        let actual: Ast = parse("[a-cx-z]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[-a]" は先頭の '-' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('-', '-'), ('a', 'a')])]);
+        let actual: Ast = parse("[-a]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[a-]" は末尾の '-' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('a', 'a'), ('-', '-')])]);
+        let actual: Ast = parse("[a-]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[z-a]" は範囲が逆転しているためエラー -----
+        let expect = Err(ParseError::InvalidClassRange(1));
+        let actual = parse("[z-a]");
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_neg_class() {
+        // ----- "[^a-c]" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::NegClass(vec![('a', 'c')])]);
+        let actual: Ast = parse("[^a-c]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[a^]" は先頭以外の '^' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![('a', 'a'), ('^', '^')])]);
+        let actual: Ast = parse("[a^]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[^^]" は否定の直後の '^' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::NegClass(vec![('^', '^')])]);
+        let actual: Ast = parse("[^^]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[]a]" は先頭の ']' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::Class(vec![(']', ']'), ('a', 'a')])]);
+        let actual: Ast = parse("[]a]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[^]]" は否定の直後の ']' を文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![Ast::NegClass(vec![(']', ']')])]);
+        let actual: Ast = parse("[^]]").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "[]" と "[^]" は閉じカッコがないためエラー -----
+        assert_eq!(parse("[]"), Err(ParseError::NoRightBracket));
+        assert_eq!(parse("[^]"), Err(ParseError::NoRightBracket));
+    }
+
+    #[test]
+    fn test_parse_contain_repeat() {
+        // ----- "a{3}" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Repeat {
+            node: Box::new(Ast::Char('a')),
+            min: 3,
+            max: Some(3),
+            greedy: true,
+        }]);
+        let actual: Ast = parse("a{3}").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a{2,}" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Repeat {
+            node: Box::new(Ast::Char('a')),
+            min: 2,
+            max: None,
+            greedy: true,
+        }]);
+        let actual: Ast = parse("a{2,}").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "(ab){2,5}" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Repeat {
+            node: Box::new(Ast::Seq(vec![Ast::Char('a'), Ast::Char('b')])),
+            min: 2,
+            max: Some(5),
+            greedy: true,
+        }]);
+        let actual: Ast = parse("(ab){2,5}").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a\{3\}" は回数指定ではなく文字として扱う -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Char('{'),
+            Ast::Char('3'),
+            Ast::Char('}'),
+        ]);
+        let actual: Ast = parse("a\\{3\\}").unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_repeat_return_err() {
+        // ----- "a{}" が入力されたケース -----
+        let expect = Err(ParseError::InvalidRepeat(1));
+        let actual = parse("a{}");
+        assert_eq!(actual, expect);
+
+        // ----- "a{x}" が入力されたケース -----
+        let expect = Err(ParseError::InvalidRepeat(1));
+        let actual = parse("a{x}");
+        assert_eq!(actual, expect);
+
+        // ----- "a{2,1}" が入力されたケース -----
+        let expect = Err(ParseError::InvalidRepeatRange(1));
+        let actual = parse("a{2,1}");
+        assert_eq!(actual, expect);
+
+        // ----- "a{18446744073709551615}" は回数が上限を超えている -----
+        let expect = Err(ParseError::RepeatTooLarge(1));
+        let actual = parse("a{18446744073709551615}");
+        assert_eq!(actual, expect);
+
+        // ----- "a{0,1001}" は回数が上限を超えている -----
+        let expect = Err(ParseError::RepeatTooLarge(1));
+        let actual = parse("a{0,1001}");
+        assert_eq!(actual, expect);
+
+        // ----- "a{1000,}" は上限ちょうどのため受け付ける -----
+        assert!(parse("a{1000,}").is_ok());
+
+        // ----- "a{99999999999999999999}" は usize に収まらないため、回数が上限を超えている -----
+        let expect = Err(ParseError::RepeatTooLarge(1));
+        let actual = parse("a{99999999999999999999}");
+        assert_eq!(actual, expect);
+
+        // ----- "((a{1000}){1000}){1000}" は入れ子の回数指定を展開すると上限を超える -----
+        let expect = Err(ParseError::RepeatTooLarge(10));
+        let actual = parse("((a{1000}){1000}){1000}");
+        assert_eq!(actual, expect);
+
+        // ----- "(a{100}){90}" は展開後の大きさが上限に収まる -----
+        assert!(parse("(a{100}){90}").is_ok());
+
+        // ----- "{3}" が入力されたケース -----
+        let expect = Err(ParseError::NoPrev(0));
+        let actual = parse("{3}");
+        assert_eq!(actual, expect);
+
+        // ----- "a{2" が入力されたケース -----
+        let expect = Err(ParseError::NoRightBrace);
+        let actual = parse("a{2");
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_anchor() {
+        // ----- "^abc$" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::StartAnchor,
+            Ast::Char('a'),
+            Ast::Char('b'),
+            Ast::Char('c'),
+            Ast::EndAnchor,
+        ]);
+        let actual: Ast = parse("^abc$").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\^" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('^')]);
+        let actual: Ast = parse("\\^").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a$b" が入力されたケース -----
+        // アンカーの位置は Seq の中でそのまま保持される
+        let expect: Ast = Ast::Seq(vec![Ast::Char('a'), Ast::EndAnchor, Ast::Char('b')]);
+        let actual: Ast = parse("a$b").unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_question() {
+        // ----- "ab?" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Question {
+                node: Box::new(Ast::Char('b')),
+                greedy: true,
+            },
+        ]);
+        let actual: Ast = parse("ab?").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a\\?" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('a'), Ast::Char('?')]);
+        let actual: Ast = parse("a\\?").unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_lazy() {
+        // ----- "a*?" と "a*" は異なる Ast になる -----
+        let expect: Ast = Ast::Seq(vec![Ast::Star {
+            node: Box::new(Ast::Char('a')),
+            greedy: false,
+        }]);
+        let actual: Ast = parse("a*?").unwrap();
+        assert_eq!(actual, expect);
+        assert_ne!(actual, parse("a*").unwrap());
+
+        // ----- "a+?" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Plus {
+            node: Box::new(Ast::Char('a')),
+            greedy: false,
+        }]);
+        let actual: Ast = parse("a+?").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a??" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![Ast::Question {
+            node: Box::new(Ast::Char('a')),
+            greedy: false,
+        }]);
+        let actual: Ast = parse("a??").unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "a{2,3}?b" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Repeat {
+                node: Box::new(Ast::Char('a')),
+                min: 2,
+                max: Some(3),
+                greedy: false,
+            },
+            Ast::Char('b'),
+        ]);
+        let actual: Ast = parse("a{2,3}?b").unwrap();
+        assert_eq!(actual, expect);
+    }
+
     #[test]
     fn test_parse_return_err() {
         // ----- "abc(def|ghi" が入力されたケース -----
@@ -264,6 +1366,42 @@ mod tests {
         let actual = parse(pattern);
         assert_eq!(actual, expect);
 
+        // ----- "?abc" が入力されたケース -----
+        let expect = Err(ParseError::NoPrev(0));
+        let pattern: &str = "?abc";
+        let actual = parse(pattern);
+        assert_eq!(actual, expect);
+
+        // ----- "+abc" が入力されたケース -----
+        let expect = Err(ParseError::NoPrev(0));
+        let pattern: &str = "+abc";
+        let actual = parse(pattern);
+        assert_eq!(actual, expect);
+
+        // ----- "あ)" はエラーの位置をバイト単位で返す -----
+        let expect = Err(ParseError::InvalidRightParen(3));
+        let pattern: &str = "あ)";
+        let actual = parse(pattern);
+        assert_eq!(actual, expect);
+
+        // ----- "ab\" のように末尾が \ のケース -----
+        let expect = Err(ParseError::TrailingBackslash(2));
+        let pattern: &str = "ab\\";
+        let actual = parse(pattern);
+        assert_eq!(actual, expect);
+
+        // ----- "[ab" が入力されたケース -----
+        let expect = Err(ParseError::NoRightBracket);
+        let pattern: &str = "[ab";
+        let actual = parse(pattern);
+        assert_eq!(actual, expect);
+
+        // ----- "ab]" が入力されたケース -----
+        let expect = Err(ParseError::InvalidRightBracket(2));
+        let pattern: &str = "ab]";
+        let actual = parse(pattern);
+        assert_eq!(actual, expect);
+
         // ----- "" が入力されたケース -----
         let expect = Err(ParseError::Empty);
         let pattern: &str = "";
@@ -276,4 +1414,175 @@ mod tests {
         let actual = parse(pattern);
         assert_eq!(actual, expect);
     }
+
+    #[test]
+    fn test_parse_with_node_limit() {
+        // ----- "abc" は Char 3 つと Seq 1 つで、ノード数は 4 -----
+        let expect: Ast = Ast::Seq(vec![Ast::Char('a'), Ast::Char('b'), Ast::Char('c')]);
+        let actual: Ast = parse_with_node_limit("abc", 4).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- 上限を超えるケース -----
+        let expect = Err(ParseError::PatternTooComplex);
+        let actual = parse_with_node_limit("abc", 3);
+        assert_eq!(actual, expect);
+
+        // ----- "a|b|c" は Char 3 つ、Seq 3 つ、Or 2 つで、ノード数は 8 -----
+        assert!(parse_with_node_limit("a|b|c", 8).is_ok());
+        let expect = Err(ParseError::PatternTooComplex);
+        let actual = parse_with_node_limit("a|b|c", 7);
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_coalesce_char_alternatives() {
+        // ----- "a|b|c" が入力されたケース -----
+        let expect: Ast = Ast::Class(vec![('a', 'a'), ('b', 'b'), ('c', 'c')]);
+        let actual: Ast = parse("a|b|c").unwrap().coalesce_char_alternatives();
+        assert_eq!(actual, expect);
+
+        // ----- "x(a|b)*" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('x'),
+            Ast::Star {
+                node: Box::new(Ast::Class(vec![('a', 'a'), ('b', 'b')])),
+                greedy: true,
+            },
+        ]);
+        let actual: Ast = parse("x(a|b)*").unwrap().coalesce_char_alternatives();
+        assert_eq!(actual, expect);
+
+        // ----- "ab|c" は 2 文字の枝を含むため、変換しない -----
+        let expect: Ast = parse("ab|c").unwrap();
+        let actual: Ast = parse("ab|c").unwrap().coalesce_char_alternatives();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_contain_text_anchor() {
+        // ----- "\Aabc\z" が入力されたケース -----
+        let expect: Ast = Ast::Seq(vec![
+            Ast::StartText,
+            Ast::Char('a'),
+            Ast::Char('b'),
+            Ast::Char('c'),
+            Ast::EndText,
+        ]);
+        let pattern: &str = "\\Aabc\\z";
+        let actual: Ast = parse(pattern).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "\Z" は未定義のエスケープ -----
+        let expect = Err(ParseError::InvalidEscape(1, 'Z'));
+        let actual = parse("\\Z");
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_parse_with_explicit_grouping() {
+        let options = ParseOptions {
+            explicit_grouping: true,
+            ..ParseOptions::default()
+        };
+
+        // ----- "ab*" が入力されたケース -----
+        // '*' は b のみに結合する
+        let expect: Ast = Ast::Seq(vec![
+            Ast::Char('a'),
+            Ast::Star {
+                node: Box::new(Ast::Group(Box::new(Ast::Char('b')))),
+                greedy: true,
+            },
+        ]);
+        let actual: Ast = parse_with_options("ab*", &options).unwrap();
+        assert_eq!(actual, expect);
+
+        // ----- "(ab)*" が入力されたケース -----
+        // '*' は ab 全体に結合する
+        let expect: Ast = Ast::Seq(vec![Ast::Star {
+            node: Box::new(Ast::Group(Box::new(Ast::Seq(vec![
+                Ast::Char('a'),
+                Ast::Char('b'),
+            ])))),
+            greedy: true,
+        }]);
+        let actual: Ast = parse_with_options("(ab)*", &options).unwrap();
+        assert_eq!(actual, expect);
+    }
+
+    #[test]
+    fn test_semantically_eq() {
+        // ----- 要素が 1 つの Seq は、中身の Ast と等しい -----
+        let seq: Ast = Ast::Seq(vec![Ast::Char('a')]);
+        let c: Ast = Ast::Char('a');
+        assert!(seq.semantically_eq(&c));
+        assert_ne!(seq, c);
+
+        // ----- ネストした Seq は平坦化して比較する -----
+        let nested: Ast = parse("a(bc)").unwrap();
+        let flat: Ast = parse("abc").unwrap();
+        assert!(nested.semantically_eq(&flat));
+        assert_ne!(nested, flat);
+
+        // ----- 意味の異なる Ast は等しくない -----
+        let left: Ast = parse("ab*").unwrap();
+        let right: Ast = parse("(ab)*").unwrap();
+        assert!(!left.semantically_eq(&right));
+    }
+
+    #[test]
+    fn test_parse_lookaround_return_err() {
+        // ----- "a(?=b)" が入力されたケース -----
+        let expect = Err(ParseError::UnsupportedLookaround(1));
+        let actual = parse("a(?=b)");
+        assert_eq!(actual, expect);
+
+        // ----- "a(?!b)" が入力されたケース -----
+        let expect = Err(ParseError::UnsupportedLookaround(1));
+        let actual = parse("a(?!b)");
+        assert_eq!(actual, expect);
+
+        // ----- "(?<=a)b" が入力されたケース -----
+        let expect = Err(ParseError::UnsupportedLookaround(0));
+        let actual = parse("(?<=a)b");
+        assert_eq!(actual, expect);
+
+        // ----- "ab(?<!a)" が入力されたケース -----
+        let expect = Err(ParseError::UnsupportedLookaround(2));
+        let actual = parse("ab(?<!a)");
+        assert_eq!(actual, expect);
+
+        // ----- エスケープした "\(?=" は先読みではない -----
+        assert!(parse("\\(?=").is_ok());
+    }
+
+    #[test]
+    fn test_parse_with_spans() {
+        // ----- "a(bc)*" が入力されたケース -----
+        let (ast, tree) = parse_with_spans("a(bc)*").unwrap();
+        assert_eq!(ast, parse("a(bc)*").unwrap());
+
+        // 全体の Seq は 0..6
+        assert_eq!(tree.span, Span { start: 0, end: 6 });
+        // 'a' は 0..1
+        assert_eq!(tree.children[0].span, Span { start: 0, end: 1 });
+        // Star は "(bc)*" 全体の 1..6
+        let star: &SpanTree = &tree.children[1];
+        assert_eq!(star.span, Span { start: 1, end: 6 });
+        // Star の中の Seq は括弧を含む 1..5、'b' は 2..3、'c' は 3..4
+        let group: &SpanTree = &star.children[0];
+        assert_eq!(group.span, Span { start: 1, end: 5 });
+        assert_eq!(group.children[0].span, Span { start: 2, end: 3 });
+        assert_eq!(group.children[1].span, Span { start: 3, end: 4 });
+
+        // ----- "a\*|[xy]{2}" が入力されたケース -----
+        let (_, tree) = parse_with_spans("a\\*|[xy]{2}").unwrap();
+        assert_eq!(tree.span, Span { start: 0, end: 11 });
+        // エスケープ "\*" は '\' を含む 1..3
+        assert_eq!(tree.children[0].children[1].span, Span { start: 1, end: 3 });
+        // Repeat は "[xy]{2}" 全体の 4..11、Class は 4..8
+        let repeat: &SpanTree = &tree.children[1].children[0];
+        assert_eq!(repeat.span, Span { start: 4, end: 11 });
+        assert_eq!(repeat.children[0].span, Span { start: 4, end: 8 });
+    }
 }
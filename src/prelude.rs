@@ -0,0 +1,21 @@
+//! よく使う型・関数をまとめて再公開するモジュール  
+//! `use regex_dfa::prelude::*;` とすることで、一般的な用途に必要なものをインポートできる。
+//!
+//! ```
+//! use regex_dfa::prelude::*;
+//!
+//! let ast: Ast = parse("a(b|c)*").unwrap();
+//! assert!(ast.semantically_eq(&parse("a((b|c))*").unwrap()));
+//!
+//! let err: ParseError = parse("(ab").unwrap_err();
+//! assert_eq!(err, ParseError::NoRightParen);
+//!
+//! let regex: Regex = Regex::new("a(b|c)*").unwrap();
+//! ```
+
+pub use crate::error::ParseError;
+pub use crate::parser::{
+    Ast, ParseOptions, Span, SpanTree, parse, parse_with_node_limit, parse_with_options,
+    parse_with_spans,
+};
+pub use crate::regex::Regex;
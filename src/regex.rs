@@ -0,0 +1,234 @@
+//! 正規表現の式をコンパイルするための型
+//! 式をパースして Ast に変換し、Ast から NFA、NFA から DFA を構築する。
+//! 構築した DFA を保持し、文字列との照合に用いる。
+
+use std::iter;
+
+use crate::{
+    automaton::{
+        dfa::{Dfa, DfaState},
+        nfa::{Nfa, NfaState},
+    },
+    error::ParseError,
+    parser::{Ast, parse},
+};
+
+/// コンパイル済みの正規表現の型
+#[derive(Debug, Clone)]
+pub struct Regex {
+    dfa: Dfa,
+}
+
+impl Regex {
+    /// 式をコンパイルし、Regex を生成
+    pub fn new(pattern: &str) -> Result<Regex, ParseError> {
+        let ast: Ast = parse(pattern)?;
+        let mut state: NfaState = 0;
+        let nfa: Nfa = Nfa::from_ast(&ast, &mut state);
+        Ok(Regex {
+            dfa: Dfa::from_nfa(&nfa),
+        })
+    }
+
+    /// 文字列全体が式にマッチするかを返す
+    pub fn is_match(&self, input: &str) -> bool {
+        self.dfa.accepts(input)
+    }
+
+    /// 文字列の中で最も左にあるマッチの範囲(バイト単位で start 以上 end 未満)を返す
+    ///
+    /// 開始位置を先頭から順に試し、最初にマッチした開始位置から最も長くマッチする範囲を返す。
+    /// マッチしない場合は None を返す。
+    pub fn find(&self, input: &str) -> Option<(usize, usize)> {
+        self.find_at(input, 0)
+    }
+
+    /// 重なり合わないマッチの範囲を、先頭から順に返すイテレータを返す
+    ///
+    /// 各マッチの終了位置から次のマッチを探す。空文字列にマッチした場合は 1 文字進めて探し、
+    /// 直前のマッチの終了位置と同じ位置の空マッチは返さない。
+    pub fn find_iter<'a>(&'a self, input: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let mut at: usize = 0;
+        let mut last_end: Option<usize> = None;
+        iter::from_fn(move || {
+            while at <= input.len() {
+                let (start, end) = self.find_at(input, at)?;
+                let empty: bool = start == end;
+                at = if empty {
+                    end + input[end..].chars().next().map_or(1, char::len_utf8)
+                } else {
+                    end
+                };
+                if empty && last_end == Some(end) {
+                    continue;
+                }
+                last_end = Some(end);
+                return Some((start, end));
+            }
+            None
+        })
+    }
+
+    /// input の at 以降で最も左にあるマッチの範囲を返す
+    fn find_at(&self, input: &str, at: usize) -> Option<(usize, usize)> {
+        let mut starts = input[at..]
+            .char_indices()
+            .map(|(i, _)| at + i)
+            .chain([input.len()]);
+        starts.find_map(|start| self.longest_match_at(input, start).map(|end| (start, end)))
+    }
+
+    /// input の start から始まる最長のマッチの終了位置を返す
+    fn longest_match_at(&self, input: &str, start: usize) -> Option<usize> {
+        let mut state: DfaState = if start == 0 {
+            self.dfa.start
+        } else {
+            self.dfa.mid_start
+        };
+        let mut end: Option<usize> = self
+            .dfa
+            .is_accept(state, start == input.len())
+            .then_some(start);
+        for (i, c) in input[start..].char_indices() {
+            match self.dfa.next_state(state, c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            let pos: usize = start + i + c.len_utf8();
+            if self.dfa.is_accept(state, pos == input.len()) {
+                end = Some(pos);
+            }
+        }
+        end
+    }
+
+    /// コンパイルした DFA を返す
+    pub fn dfa(&self) -> &Dfa {
+        &self.dfa
+    }
+}
+
+// ----- テストコード・試し -----
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn test_is_match() {
+        // ----- "abc" が入力されたケース -----
+        let regex: Regex = Regex::new("abc").unwrap();
+        assert!(regex.is_match("abc"));
+        assert!(!regex.is_match("abcd"));
+        assert!(!regex.is_match("ab"));
+        assert!(!regex.is_match(""));
+
+        // ----- "a(b|c)*d" が入力されたケース -----
+        let regex: Regex = Regex::new("a(b|c)*d").unwrap();
+        assert!(regex.is_match("ad"));
+        assert!(regex.is_match("abccbd"));
+        assert!(!regex.is_match("abxd"));
+
+        // ----- "a|" と "(a|)b" は空の選択肢にもマッチする -----
+        let regex: Regex = Regex::new("a|").unwrap();
+        assert!(regex.is_match(""));
+        assert!(regex.is_match("a"));
+        let regex: Regex = Regex::new("(a|)b").unwrap();
+        assert!(regex.is_match("b"));
+        assert!(regex.is_match("ab"));
+
+        // ----- "a()*" と "()" は空のグループを空文字列として扱う -----
+        let regex: Regex = Regex::new("a()*").unwrap();
+        assert!(regex.is_match("a"));
+        assert!(!regex.is_match("aa"));
+        let regex: Regex = Regex::new("()").unwrap();
+        assert!(regex.is_match(""));
+        assert!(!regex.is_match("a"));
+    }
+
+    #[test]
+    fn test_is_match_anchor_and_any_char() {
+        // ----- "^a.c$" が入力されたケース -----
+        let regex: Regex = Regex::new("^a.c$").unwrap();
+        assert!(regex.is_match("abc"));
+        assert!(regex.is_match("a-c"));
+        assert!(!regex.is_match("ac"));
+
+        // ----- "a$b" の $ は入力の末尾でのみ通れる -----
+        let regex: Regex = Regex::new("a$b").unwrap();
+        assert!(!regex.is_match("ab"));
+
+        // ----- "[^a]" は a 以外の 1 文字にマッチする -----
+        let regex: Regex = Regex::new("[^a]").unwrap();
+        assert!(regex.is_match("b"));
+        assert!(regex.is_match("^"));
+        assert!(!regex.is_match("a"));
+        assert!(!regex.is_match(""));
+    }
+
+    #[test]
+    fn test_find() {
+        // ----- "bc" が入力されたケース -----
+        let regex: Regex = Regex::new("bc").unwrap();
+        assert_eq!(regex.find("abcd"), Some((1, 3)));
+        assert_eq!(regex.find("abd"), None);
+        // 位置はバイト単位で返す
+        assert_eq!(regex.find("あbc"), Some((3, 5)));
+
+        // ----- "a+" は最も左の開始位置から最長の範囲を返す -----
+        let regex: Regex = Regex::new("a+").unwrap();
+        assert_eq!(regex.find("baaab"), Some((1, 4)));
+
+        // ----- "a*" は空文字列にマッチする -----
+        let regex: Regex = Regex::new("a*").unwrap();
+        assert_eq!(regex.find("bbb"), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_find_anchor() {
+        // ----- "^a" は入力の先頭でのみマッチする -----
+        let regex: Regex = Regex::new("^a").unwrap();
+        assert_eq!(regex.find("ab"), Some((0, 1)));
+        assert_eq!(regex.find("ba"), None);
+
+        // ----- "a$" は入力の末尾でのみマッチする -----
+        let regex: Regex = Regex::new("a$").unwrap();
+        assert_eq!(regex.find("aba"), Some((2, 3)));
+        assert_eq!(regex.find("ab"), None);
+
+        // ----- "$^" などは空の入力でのみ、末尾・先頭の順にアンカーを通れる -----
+        for pattern in ["$^", "($|$)+^", "(b1|$^){2,}"] {
+            let regex: Regex = Regex::new(pattern).unwrap();
+            assert!(regex.is_match(""), "{pattern}");
+            assert_eq!(regex.find(""), Some((0, 0)), "{pattern}");
+        }
+        let regex: Regex = Regex::new("$^").unwrap();
+        assert!(!regex.is_match("a"));
+        assert_eq!(regex.find("a"), None);
+        // 入力の先頭でない位置では通れない
+        let regex: Regex = Regex::new("a$^").unwrap();
+        assert_eq!(regex.find("a"), None);
+    }
+
+    #[test]
+    fn test_find_iter() {
+        // ----- "ab" が入力されたケース -----
+        let regex: Regex = Regex::new("ab").unwrap();
+        assert_eq!(regex.find_iter("ababab").count(), 3);
+        let actual: Vec<(usize, usize)> = regex.find_iter("xabyab").collect();
+        assert_eq!(actual, vec![(1, 3), (4, 6)]);
+
+        // ----- "a*" は空マッチでも 1 文字ずつ進む -----
+        let regex: Regex = Regex::new("a*").unwrap();
+        let actual: Vec<(usize, usize)> = regex.find_iter("bab").collect();
+        assert_eq!(actual, vec![(0, 0), (1, 2), (3, 3)]);
+        let actual: Vec<(usize, usize)> = regex.find_iter("aa").collect();
+        assert_eq!(actual, vec![(0, 2)]);
+        let actual: Vec<(usize, usize)> = regex.find_iter("").collect();
+        assert_eq!(actual, vec![(0, 0)]);
+
+        // ----- "^a" は入力の先頭でのみマッチする -----
+        let regex: Regex = Regex::new("^a").unwrap();
+        assert_eq!(regex.find_iter("aaa").count(), 1);
+    }
+}
@@ -0,0 +1,42 @@
+use regex_dfa::{
+    automaton::{
+        dfa::Dfa,
+        nfa::{Nfa, NfaState},
+    },
+    parser::{Ast, parse},
+};
+
+/// 式から DFA を構築する
+fn build(pattern: &str) -> Dfa {
+    let ast: Ast = parse(pattern).unwrap();
+    let mut state: NfaState = 0;
+    let nfa: Nfa = Nfa::from_ast(&ast, &mut state);
+    Dfa::from_nfa(&nfa)
+}
+
+#[test]
+fn test_parse_to_dfa() {
+    let dfa: Dfa = build("abc(def|ghi)");
+
+    assert!(dfa.accepts("abcdef"));
+    assert!(dfa.accepts("abcghi"));
+    assert!(!dfa.accepts("abcdeg"));
+    assert!(!dfa.accepts("abc"));
+}
+
+#[test]
+fn test_quantified_class() {
+    let dfa: Dfa = build("[a-z]+");
+    assert!(dfa.accepts("abc"));
+    assert!(!dfa.accepts(""));
+    assert!(!dfa.accepts("abC"));
+
+    let dfa: Dfa = build("[abc]*");
+    assert!(dfa.accepts(""));
+    assert!(dfa.accepts("aabbcc"));
+    assert!(!dfa.accepts("abcd"));
+
+    let dfa: Dfa = build("[一-龥]+x");
+    assert!(dfa.accepts("漢字x"));
+    assert!(!dfa.accepts("かなx"));
+}
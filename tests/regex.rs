@@ -0,0 +1,10 @@
+use regex_dfa::prelude::*;
+
+#[test]
+fn test_regex_new() {
+    let regex: Regex = Regex::new("abc(def|ghi)").unwrap();
+    assert!(!regex.dfa().accepts.is_empty());
+
+    let err: ParseError = Regex::new("abc(def").unwrap_err();
+    assert_eq!(err, ParseError::NoRightParen);
+}